@@ -0,0 +1,163 @@
+//! Account profiles
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Currency, Error, Strike};
+
+/// Currency an account supports
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCurrency {
+    /// Currency
+    pub currency: Currency,
+    /// Default currency of the account
+    pub is_default_currency: bool,
+    /// Currency is available
+    pub is_available: bool,
+    /// Invoices can be issued in currency
+    pub is_invoiceable: bool,
+}
+
+/// Account profile
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProfile {
+    /// Account id
+    pub id: String,
+    /// Handle
+    pub handle: String,
+    /// Avatar url
+    pub avatar_url: Option<String>,
+    /// Description
+    pub description: Option<String>,
+    /// Account can receive payments
+    pub can_receive: bool,
+    /// Supported currencies
+    #[serde(default)]
+    pub currencies: Vec<AccountCurrency>,
+}
+
+impl AccountProfile {
+    /// Account can receive payments in `currency`
+    pub fn can_receive_currency(&self, currency: &Currency) -> bool {
+        self.can_receive
+            && self
+                .currencies
+                .iter()
+                .any(|c| &c.currency == currency && c.is_available)
+    }
+}
+
+impl Strike {
+    /// Get account profile by handle
+    pub async fn get_account_profile_by_handle(&self, handle: &str) -> Result<AccountProfile> {
+        let url = self.url(&["v1", "accounts", "handle", handle, "profile"])?;
+
+        self.get_account_profile(url).await
+    }
+
+    /// Get account profile by account id
+    pub async fn get_account_profile_by_id(&self, account_id: &str) -> Result<AccountProfile> {
+        let url = self.url(&["v1", "accounts", account_id, "profile"])?;
+
+        self.get_account_profile(url).await
+    }
+
+    async fn get_account_profile(&self, url: reqwest::Url) -> Result<AccountProfile> {
+        let res = self.make_get(url).await?;
+
+        if is_not_found(&res) {
+            bail!(Error::NotFound);
+        }
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response getting account profile");
+                log::error!("{}", res);
+                bail!("Could not get account profile")
+            }
+        }
+    }
+}
+
+// Strike error bodies carry the status, e.g.
+// `{"data": {"status": 404, "code": "NOT_FOUND"}}`
+fn is_not_found(res: &Value) -> bool {
+    res.pointer("/data/status").and_then(Value::as_u64) == Some(404)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn profile() -> Value {
+        json!({
+            "id": "account-1",
+            "handle": "alice",
+            "avatarUrl": null,
+            "description": null,
+            "canReceive": true,
+            "currencies": [
+                {
+                    "currency": "BTC",
+                    "isDefaultCurrency": true,
+                    "isAvailable": true,
+                    "isInvoiceable": true
+                },
+                {
+                    "currency": "USD",
+                    "isDefaultCurrency": false,
+                    "isAvailable": false,
+                    "isInvoiceable": false
+                }
+            ]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_profile() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, profile())
+            .respond_json(200, profile());
+        let strike = fake.client();
+
+        let by_handle = strike.get_account_profile_by_handle("alice").await.unwrap();
+        let by_id = strike.get_account_profile_by_id("account-1").await.unwrap();
+
+        assert_eq!(by_handle, by_id);
+        assert_eq!(by_handle.handle, "alice");
+        assert!(by_handle.can_receive_currency(&Currency::BTC));
+        assert!(!by_handle.can_receive_currency(&Currency::USD));
+        assert!(!by_handle.can_receive_currency(&Currency::EUR));
+        assert_eq!(
+            fake.calls(),
+            [
+                "GET /v1/accounts/handle/alice/profile",
+                "GET /v1/accounts/account-1/profile"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_profile_not_found() {
+        let fake = FakeTransport::default();
+        fake.respond_json(
+            404,
+            json!({ "data": { "status": 404, "code": "NOT_FOUND" } }),
+        );
+
+        let err = fake
+            .client()
+            .get_account_profile_by_handle("nobody")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err.downcast_ref::<Error>(), Some(Error::NotFound)));
+    }
+}
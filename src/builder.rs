@@ -0,0 +1,315 @@
+//! Strike client builder
+
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::bail;
+#[cfg(feature = "webhooks")]
+use rand::distributions::Alphanumeric;
+#[cfg(feature = "webhooks")]
+use rand::Rng;
+use reqwest::{Client, Proxy, Url};
+
+use crate::rate_limit::Throttle;
+use crate::{Environment, ReqwestTransport, RetryPolicy, Secret, Strike, Transport};
+
+/// Default Strike api url
+pub const DEFAULT_API_URL: &str = "https://api.strike.me";
+
+/// Strike client builder
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use strike_rs::Strike;
+///
+/// let client = Strike::builder("xxxxxxxxxxx")
+///     .timeout(Duration::from_secs(30))
+///     .user_agent("my-app/1.0")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct StrikeBuilder {
+    api_key: Secret,
+    base_url: Option<String>,
+    environment: Option<Environment>,
+    deny_production_payments: bool,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+    client: Option<Client>,
+    transport: Option<Arc<dyn Transport>>,
+    #[cfg(feature = "webhooks")]
+    webhook_secret: Option<Secret>,
+    retry_policy: RetryPolicy,
+    throttle: Option<(u32, Duration)>,
+}
+
+impl StrikeBuilder {
+    /// Create builder for `api_key`
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: Secret::new(api_key),
+            base_url: None,
+            environment: None,
+            deny_production_payments: false,
+            connect_timeout: None,
+            timeout: None,
+            user_agent: None,
+            proxy: None,
+            client: None,
+            transport: None,
+            #[cfg(feature = "webhooks")]
+            webhook_secret: None,
+            retry_policy: RetryPolicy::default(),
+            throttle: None,
+        }
+    }
+
+    /// Api url, defaults to [`DEFAULT_API_URL`]
+    ///
+    /// Sets an [`Environment::Custom`], cannot be combined with
+    /// [`StrikeBuilder::environment`]
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = Some(url.to_string());
+        self
+    }
+
+    /// [`Environment`], defaults to [`Environment::Production`]
+    ///
+    /// Sandbox clients refuse payments that could reach production, see
+    /// [`StrikeBuilder::deny_production_payments`]
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// Refuse to execute payments, exchanges and payouts that could reach the
+    /// production api
+    ///
+    /// Only the sandbox api is paid. Custom urls count as production as they
+    /// may proxy to it, and handles are never paid as their lightning
+    /// addresses resolve on production. Calls fail with
+    /// [`Error::ProductionPaymentDenied`](crate::Error::ProductionPaymentDenied)
+    /// before anything is sent.
+    ///
+    /// On for [`Environment::Sandbox`], guards tests whose api url comes from
+    /// configuration.
+    pub fn deny_production_payments(mut self) -> Self {
+        self.deny_production_payments = true;
+        self
+    }
+
+    /// Timeout for establishing connections
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for each request, from connecting until the body is read
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// User agent sent with each request
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Proxy all requests, `http`, `https` and `socks5` urls are supported
+    pub fn proxy(mut self, proxy_url: &str) -> Self {
+        self.proxy = Some(proxy_url.to_string());
+        self
+    }
+
+    /// Use an existing [`Client`]
+    ///
+    /// Cannot be combined with the timeout, user agent and proxy options,
+    /// configure those on the client instead.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Send requests through `transport` in place of a [`Client`]
+    ///
+    /// Cannot be combined with a custom client or the timeout, user agent
+    /// and proxy options.
+    pub fn transport<T>(mut self, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Webhook secret shared with Strike, defaults to a random secret
+    #[cfg(feature = "webhooks")]
+    pub fn webhook_secret(mut self, secret: &str) -> Self {
+        self.webhook_secret = Some(Secret::new(secret));
+        self
+    }
+
+    /// [`RetryPolicy`], defaults to three attempts with exponential backoff
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Send at most `max_requests` every `per`, in bursts of up to
+    /// `max_requests`
+    ///
+    /// Requests wait for capacity rather than fail. The limit is shared by
+    /// clones of the built client.
+    pub fn throttle(mut self, max_requests: u32, per: Duration) -> Self {
+        self.throttle = Some((max_requests, per));
+        self
+    }
+
+    /// Build Strike client
+    pub fn build(self) -> anyhow::Result<Strike> {
+        let environment = match (self.environment, self.base_url) {
+            (Some(_), Some(_)) => {
+                bail!("Base url cannot be set with an environment, use a custom environment")
+            }
+            (Some(environment), None) => environment,
+            (None, Some(url)) => Environment::Custom(Url::from_str(&url)?),
+            (None, None) => Environment::Production,
+        };
+        let base_url = environment.url();
+        let deny_production_payments =
+            self.deny_production_payments || environment == Environment::Sandbox;
+
+        if let Some((_, per)) = self.throttle {
+            if per.is_zero() {
+                bail!("Throttle period must be greater than zero");
+            }
+        }
+
+        let client_options = self.connect_timeout.is_some()
+            || self.timeout.is_some()
+            || self.user_agent.is_some()
+            || self.proxy.is_some();
+
+        let transport: Arc<dyn Transport> = match (self.transport, self.client) {
+            (Some(_), client) if client.is_some() || client_options => {
+                bail!("Client and its options cannot be set with a custom transport")
+            }
+            (Some(transport), _) => transport,
+            (None, Some(_)) if client_options => {
+                bail!("Timeouts, user agent and proxy cannot be set with a custom client")
+            }
+            (None, Some(client)) => Arc::new(ReqwestTransport::new(client)),
+            (None, None) => {
+                let mut builder = Client::builder();
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(user_agent) = self.user_agent {
+                    builder = builder.user_agent(user_agent);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(Proxy::all(proxy)?);
+                }
+                Arc::new(ReqwestTransport::new(builder.build()?))
+            }
+        };
+
+        #[cfg(feature = "webhooks")]
+        let webhook_secret = match self.webhook_secret {
+            Some(secret) => secret,
+            None => Secret::new(
+                rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(15)
+                    .map(char::from)
+                    .collect::<String>(),
+            ),
+        };
+
+        Ok(Strike {
+            api_key: self.api_key,
+            base_url,
+            environment,
+            deny_production_payments,
+            transport,
+            #[cfg(feature = "webhooks")]
+            webhook_secret,
+            retry_policy: self.retry_policy,
+            retry_executions: false,
+            idempotency_key: Arc::new(Mutex::new(None)),
+            rate_limit: Arc::new(Mutex::new(None)),
+            throttle: self
+                .throttle
+                .map(|(max_requests, per)| Arc::new(Throttle::new(max_requests, per))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn error(builder: StrikeBuilder) -> String {
+        builder.build().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_transport_conflicts() {
+        let builder = || Strike::builder("key").transport(FakeTransport::default());
+
+        assert!(builder().build().is_ok());
+        assert_eq!(
+            error(builder().client(Client::new())),
+            "Client and its options cannot be set with a custom transport"
+        );
+        assert_eq!(
+            error(builder().timeout(Duration::from_secs(1))),
+            "Client and its options cannot be set with a custom transport"
+        );
+        assert_eq!(
+            error(builder().proxy("http://localhost:8080")),
+            "Client and its options cannot be set with a custom transport"
+        );
+    }
+
+    #[test]
+    fn test_client_conflicts() {
+        let builder = || Strike::builder("key").client(Client::new());
+
+        assert!(builder().build().is_ok());
+        for builder in [
+            builder().connect_timeout(Duration::from_secs(1)),
+            builder().timeout(Duration::from_secs(1)),
+            builder().user_agent("my-app/1.0"),
+            builder().proxy("http://localhost:8080"),
+        ] {
+            assert_eq!(
+                error(builder),
+                "Timeouts, user agent and proxy cannot be set with a custom client"
+            );
+        }
+    }
+
+    #[test]
+    fn test_zero_throttle_period() {
+        assert_eq!(
+            error(Strike::builder("key").throttle(10, Duration::ZERO)),
+            "Throttle period must be greater than zero"
+        );
+        assert!(Strike::builder("key")
+            .throttle(10, Duration::from_secs(1))
+            .build()
+            .is_ok());
+    }
+}
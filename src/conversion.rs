@@ -0,0 +1,294 @@
+//! Currency conversion
+
+use anyhow::{bail, Result};
+
+use crate::{Amount, ConversionRate, Currency};
+
+/// Rounding mode used when converting amounts
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round toward zero
+    Down,
+    /// Round away from zero
+    Up,
+    /// Round to nearest, halves away from zero
+    Nearest,
+}
+
+impl Rounding {
+    /// Round `value` to `decimals` decimal places
+    pub fn round(&self, value: f64, decimals: u32) -> f64 {
+        let scale = 10f64.powi(decimals as i32);
+        let scaled = value * scale;
+
+        // Float noise such as 0.1 * 3 = 0.30000000000000004 should not be
+        // rounded up a whole unit. The tolerance is a few ulps, so it stays
+        // far below one unit for large amounts.
+        let nearest = scaled.round();
+        if (scaled - nearest).abs() <= 8.0 * f64::EPSILON * scaled.abs().max(1.0) {
+            return nearest / scale;
+        }
+
+        let rounded = match self {
+            Self::Down => scaled.trunc(),
+            Self::Up => match scaled.is_sign_negative() {
+                true => scaled.floor(),
+                false => scaled.ceil(),
+            },
+            Self::Nearest => nearest,
+        };
+
+        rounded / scale
+    }
+}
+
+impl Currency {
+    /// Decimal places amounts in currency are expressed in
+    pub fn decimals(&self) -> u32 {
+        match self {
+            Self::BTC => 8,
+            Self::USD | Self::EUR | Self::GBP | Self::AUD | Self::USDT => 2,
+        }
+    }
+}
+
+impl Amount {
+    /// Convert to `target` currency with `rate`
+    ///
+    /// `rate` is the price of one `source_currency` in `target_currency` and
+    /// may be used in either direction.
+    pub fn convert(
+        &self,
+        target: Currency,
+        rate: &ConversionRate,
+        rounding: Rounding,
+    ) -> Result<Amount> {
+        if self.currency == target {
+            return Ok(self.clone());
+        }
+
+        if rate.amount <= 0.0 {
+            bail!("Invalid conversion rate: {}", rate.amount);
+        }
+
+        let amount = if self.currency == rate.source_currency && target == rate.target_currency {
+            self.amount * rate.amount
+        } else if self.currency == rate.target_currency && target == rate.source_currency {
+            self.amount / rate.amount
+        } else {
+            bail!(
+                "Rate from {} to {} cannot convert {} to {}",
+                rate.source_currency,
+                rate.target_currency,
+                self.currency,
+                target
+            );
+        };
+
+        Ok(Amount {
+            amount: rounding.round(amount, target.decimals()),
+            currency: target,
+        })
+    }
+
+    /// Convert to sats with `rate`
+    pub fn to_sats_with_rate(&self, rate: &ConversionRate, rounding: Rounding) -> Result<u64> {
+        sats(&self.convert(Currency::BTC, rate, rounding)?)
+    }
+}
+
+// Sats of a BTC amount already rounded to 8 decimals, `Amount::to_sats`
+// truncates float noise such as 0.29 BTC = 28999999.999 sats
+fn sats(amount: &Amount) -> Result<u64> {
+    match amount.currency {
+        Currency::BTC => Ok((amount.amount * 100_000_000.0).round() as u64),
+        _ => bail!("Unit cannot be converted to sats"),
+    }
+}
+
+/// Responses that carry the [`ConversionRate`] Strike quoted
+pub trait QuotedConversion {
+    /// Quoted conversion rate, if the response involved a conversion
+    fn conversion_rate(&self) -> Option<&ConversionRate>;
+
+    /// Convert `amount` to `target` at the quoted rate
+    fn convert_amount(
+        &self,
+        amount: &Amount,
+        target: Currency,
+        rounding: Rounding,
+    ) -> Result<Amount> {
+        if amount.currency == target {
+            return Ok(amount.clone());
+        }
+
+        match self.conversion_rate() {
+            Some(rate) => amount.convert(target, rate, rounding),
+            None => bail!(
+                "No conversion rate to convert {} to {}",
+                amount.currency,
+                target
+            ),
+        }
+    }
+
+    /// Convert `amount` to sats at the quoted rate
+    fn amount_in_sats(&self, amount: &Amount, rounding: Rounding) -> Result<u64> {
+        sats(&self.convert_amount(amount, Currency::BTC, rounding)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::PayInvoiceQuoteResponse;
+
+    fn btc_usd() -> ConversionRate {
+        ConversionRate {
+            amount: 26525.70,
+            source_currency: Currency::BTC,
+            target_currency: Currency::USD,
+        }
+    }
+
+    #[test]
+    fn test_convert() {
+        let sats = Amount::from_sats(100_000);
+
+        let usd = sats
+            .convert(Currency::USD, &btc_usd(), Rounding::Nearest)
+            .unwrap();
+        assert_eq!(usd.currency, Currency::USD);
+        assert_eq!(usd.amount, 26.53);
+
+        let usd = sats
+            .convert(Currency::USD, &btc_usd(), Rounding::Down)
+            .unwrap();
+        assert_eq!(usd.amount, 26.52);
+
+        let dollar = Amount {
+            currency: Currency::USD,
+            amount: 1.0,
+        };
+        assert_eq!(
+            dollar
+                .to_sats_with_rate(&btc_usd(), Rounding::Down)
+                .unwrap(),
+            3769
+        );
+        assert_eq!(
+            dollar.to_sats_with_rate(&btc_usd(), Rounding::Up).unwrap(),
+            3770
+        );
+    }
+
+    #[test]
+    fn test_convert_wrong_rate() {
+        let euro = Amount {
+            currency: Currency::EUR,
+            amount: 1.0,
+        };
+        assert!(euro
+            .convert(Currency::BTC, &btc_usd(), Rounding::Nearest)
+            .is_err());
+    }
+
+    #[test]
+    fn test_rounding_noise() {
+        assert_eq!(Rounding::Up.round(0.1 * 3.0, 2), 0.3);
+        assert_eq!(Rounding::Down.round(0.000_000_03, 8), 0.000_000_03);
+        assert_eq!(
+            Amount {
+                currency: Currency::USD,
+                amount: 0.29,
+            }
+            .to_sats_with_rate(
+                &ConversionRate {
+                    amount: 1.0,
+                    source_currency: Currency::BTC,
+                    target_currency: Currency::USD,
+                },
+                Rounding::Down
+            )
+            .unwrap(),
+            29_000_000
+        );
+    }
+
+    #[test]
+    fn test_rounding_large_amounts() {
+        assert_eq!(Rounding::Down.round(10.123456789, 8), 10.12345678);
+        assert_eq!(Rounding::Up.round(10.123456789, 8), 10.12345679);
+        assert_eq!(Rounding::Down.round(1_234_567.891, 2), 1_234_567.89);
+        assert_eq!(Rounding::Up.round(1_234_567.891, 2), 1_234_567.9);
+
+        let rate = ConversionRate {
+            amount: 65432.1,
+            source_currency: Currency::BTC,
+            target_currency: Currency::USD,
+        };
+        let million = Amount {
+            currency: Currency::USD,
+            amount: 1_000_000.0,
+        };
+        assert_eq!(
+            million.to_sats_with_rate(&rate, Rounding::Down).unwrap(),
+            1_528_301_857
+        );
+        assert_eq!(
+            million.to_sats_with_rate(&rate, Rounding::Up).unwrap(),
+            1_528_301_858
+        );
+
+        let btc = Amount {
+            currency: Currency::BTC,
+            amount: 150.123_456_78,
+        };
+        let usd = btc.convert(Currency::USD, &rate, Rounding::Down).unwrap();
+        assert_eq!(usd.amount, 9_822_893.03);
+        let usd = btc.convert(Currency::USD, &rate, Rounding::Up).unwrap();
+        assert_eq!(usd.amount, 9_822_893.04);
+    }
+
+    #[test]
+    fn test_quoted_rate_direction() {
+        // USD payer on a BTC invoice, Strike's rate labelled from either side
+        for (source, target) in [("USD", "BTC"), ("BTC", "USD")] {
+            let quote: PayInvoiceQuoteResponse = serde_json::from_value(json!({
+                "paymentQuoteId": "quote-1",
+                "description": null,
+                "validUntil": "2023-03-16T15:33:33.6166667+00:00",
+                "conversionRate": {
+                    "amount": "27015.66",
+                    "sourceCurrency": source,
+                    "targetCurrency": target
+                },
+                "amount": { "amount": "0.10", "currency": "USD" },
+                "lightningNetworkFee": { "amount": "0.00", "currency": "USD" },
+                "totalAmount": { "amount": "0.10", "currency": "USD" }
+            }))
+            .unwrap();
+
+            let rate = quote.conversion_rate().unwrap();
+            assert_eq!(rate.source_currency, Currency::BTC);
+            assert_eq!(rate.target_currency, Currency::USD);
+            assert_eq!(
+                quote
+                    .amount_in_sats(&quote.amount, Rounding::Nearest)
+                    .unwrap(),
+                370
+            );
+        }
+
+        // A fiat price in BTC keeps its direction
+        let rate: ConversionRate = serde_json::from_value(json!({
+            "amount": "0.000037",
+            "sourceCurrency": "USD",
+            "targetCurrency": "BTC"
+        }))
+        .unwrap();
+        assert_eq!(rate.source_currency, Currency::USD);
+    }
+}
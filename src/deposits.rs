@@ -0,0 +1,263 @@
+//! Deposits
+
+use std::time::SystemTime;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{append_list_query, Amount, FeePolicy, Filter, Idempotent, Page, Strike};
+
+/// Deposit request
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositRequest {
+    /// Bank payment method to fund from
+    pub payment_method_id: String,
+    /// Amount in the payment method's currency, e.g. `"100.00"`
+    pub amount: String,
+    /// Fee policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_policy: Option<FeePolicy>,
+}
+
+/// Deposit state
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DepositState {
+    /// Deposit pending
+    Pending,
+    /// Deposit completed
+    Completed,
+    /// Deposit failed
+    Failed,
+    /// Deposit reversed by the bank
+    Reversed,
+}
+
+impl DepositState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "PENDING",
+            Self::Completed => "COMPLETED",
+            Self::Failed => "FAILED",
+            Self::Reversed => "REVERSED",
+        }
+    }
+}
+
+/// Deposit response
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositResponse {
+    /// Deposit id
+    pub id: String,
+    /// Payment method id
+    pub payment_method_id: String,
+    /// Deposit [`Amount`]
+    pub amount: Amount,
+    /// Fee
+    pub fee: Option<Amount>,
+    /// Amount credited including fee
+    pub total_amount: Option<Amount>,
+    /// Deposit state
+    pub state: DepositState,
+    /// Failure reason
+    pub failure_reason: Option<String>,
+    /// Created timestamp
+    pub created: String,
+    /// Completed timestamp
+    pub completed: Option<String>,
+}
+
+/// Deposits query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DepositsQuery {
+    /// Only return deposits in this state
+    pub state: Option<DepositState>,
+    /// Only return deposits from this payment method
+    pub payment_method_id: Option<String>,
+    /// Only return deposits created at or after this time
+    pub created_from: Option<SystemTime>,
+    /// Only return deposits created before this time
+    pub created_before: Option<SystemTime>,
+    /// Number of deposits to skip
+    pub skip: Option<u32>,
+    /// Max number of deposits to return
+    pub top: Option<u32>,
+}
+
+impl DepositsQuery {
+    fn filter(&self) -> Vec<String> {
+        Filter::default()
+            .eq("state", self.state.as_ref().map(|state| state.as_str()))
+            .eq("paymentMethodId", self.payment_method_id.as_deref())
+            .created(self.created_from, self.created_before)
+            .into_clauses()
+    }
+}
+
+impl Strike {
+    /// Initiate deposit from a bank payment method
+    pub async fn create_deposit(
+        &self,
+        deposit_request: DepositRequest,
+    ) -> Result<Idempotent<DepositResponse>> {
+        let url = self.url(&["v1", "deposits"])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(
+                url,
+                Some(serde_json::to_value(deposit_request)?),
+                &idempotency_key,
+            )
+            .await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on deposit creation");
+                log::error!("{}", res);
+                bail!("Could not create deposit")
+            }
+        }
+    }
+
+    /// Get deposit by id
+    pub async fn get_deposit(&self, deposit_id: &str) -> Result<DepositResponse> {
+        let url = self.url(&["v1", "deposits", deposit_id])?;
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on get deposit");
+                log::error!("{}", res);
+                bail!("Could not get deposit")
+            }
+        }
+    }
+
+    /// List deposits, newest first
+    pub async fn get_deposits(&self, query: &DepositsQuery) -> Result<Page<DepositResponse>> {
+        let mut url = self.url(&["v1", "deposits"])?;
+        append_list_query(
+            &mut url,
+            &query.filter(),
+            "created desc",
+            query.skip,
+            query.top,
+        );
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on list deposits");
+                log::error!("{}", res);
+                bail!("Could not list deposits")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn deposit(state: &str) -> serde_json::Value {
+        json!({
+            "id": "deposit-1",
+            "paymentMethodId": "pm-1",
+            "amount": { "currency": "USD", "amount": "100.00" },
+            "fee": null,
+            "totalAmount": null,
+            "state": state,
+            "failureReason": null,
+            "created": "2024-01-01T00:00:00+00:00",
+            "completed": null
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_deposit() {
+        let fake = FakeTransport::default();
+        fake.respond_json(201, deposit("PENDING"));
+
+        let res = fake
+            .client()
+            .create_deposit(DepositRequest {
+                payment_method_id: "pm-1".to_string(),
+                amount: "100.00".to_string(),
+                fee_policy: Some(FeePolicy::Exclusive),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(res.state, DepositState::Pending);
+        assert_eq!(fake.calls(), ["POST /v1/deposits"]);
+        assert_eq!(
+            fake.bodies(),
+            [json!({
+                "paymentMethodId": "pm-1",
+                "amount": "100.00",
+                "feePolicy": "EXCLUSIVE"
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_deposits() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, json!({ "items": [deposit("COMPLETED")], "count": 11 }));
+
+        let page = fake
+            .client()
+            .get_deposits(&DepositsQuery {
+                state: Some(DepositState::Completed),
+                created_before: Some(UNIX_EPOCH + Duration::from_secs(1_704_067_200)),
+                skip: Some(10),
+                top: Some(5),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(page.count, 11);
+        assert_eq!(page.items[0].state, DepositState::Completed);
+
+        let query = &fake.queries()[0];
+        assert_eq!(
+            query["$filter"],
+            "state eq 'COMPLETED' and created lt 2024-01-01T00:00:00+00:00"
+        );
+        assert_eq!(query["$orderby"], "created desc");
+        assert_eq!(query["$skip"], "10");
+        assert_eq!(query["$top"], "5");
+    }
+
+    #[test]
+    fn test_filter_escapes_quotes() {
+        let query = DepositsQuery {
+            state: Some(DepositState::Pending),
+            payment_method_id: Some("pm' or state eq 'FAILED".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.filter(),
+            [
+                "state eq 'PENDING'",
+                "paymentMethodId eq 'pm'' or state eq ''FAILED'"
+            ]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Api environments
+
+use std::fmt;
+use std::str::FromStr;
+
+use reqwest::Url;
+
+use crate::builder::DEFAULT_API_URL;
+
+/// Strike sandbox api url
+pub const SANDBOX_API_URL: &str = "https://api.dev.strike.me";
+
+// Host of the production api
+const PRODUCTION_HOST: &str = "api.strike.me";
+
+// Host of the sandbox api
+const SANDBOX_HOST: &str = "api.dev.strike.me";
+
+// Domain of the lightning addresses of production handles
+const PRODUCTION_HANDLE_DOMAIN: &str = "strike.me";
+
+/// Strike environment the client talks to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Environment {
+    /// Production api at [`DEFAULT_API_URL`]
+    #[default]
+    Production,
+    /// Sandbox api at [`SANDBOX_API_URL`], payments do not move real funds
+    Sandbox,
+    /// Other api url, such as a proxy or mock server
+    Custom(Url),
+}
+
+impl Environment {
+    /// Base url of the api
+    pub fn url(&self) -> Url {
+        match self {
+            // Both constants are valid urls
+            Self::Production => Url::parse(DEFAULT_API_URL).expect("Valid production url"),
+            Self::Sandbox => Url::parse(SANDBOX_API_URL).expect("Valid sandbox url"),
+            Self::Custom(url) => url.clone(),
+        }
+    }
+
+    /// Whether requests reach the production api
+    ///
+    /// A custom url pointing at the production host counts as production
+    pub fn is_production(&self) -> bool {
+        is_production_url(&self.url())
+    }
+
+    // Lightning address domain of handles, only production serves them
+    pub(crate) fn handle_domain(&self) -> Option<&'static str> {
+        self.is_production().then_some(PRODUCTION_HANDLE_DOMAIN)
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Production => write!(f, "production"),
+            Self::Sandbox => write!(f, "sandbox"),
+            Self::Custom(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+impl FromStr for Environment {
+    type Err = anyhow::Error;
+
+    /// `production`, `sandbox` or an api url
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "production" => Ok(Self::Production),
+            "sandbox" => Ok(Self::Sandbox),
+            _ => Ok(Self::Custom(Url::parse(s.trim())?)),
+        }
+    }
+}
+
+impl From<Url> for Environment {
+    fn from(url: Url) -> Self {
+        Self::Custom(url)
+    }
+}
+
+pub(crate) fn is_production_url(url: &Url) -> bool {
+    url.host_str() == Some(PRODUCTION_HOST)
+}
+
+pub(crate) fn is_sandbox_url(url: &Url) -> bool {
+    url.host_str() == Some(SANDBOX_HOST)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+    use crate::{Amount, Currency, Error, PayHandleQuoteRequest, Strike};
+
+    #[test]
+    fn test_environment_urls() {
+        assert_eq!(Environment::default(), Environment::Production);
+        assert_eq!(
+            Environment::Production.url().as_str(),
+            "https://api.strike.me/"
+        );
+        assert_eq!(
+            Environment::Sandbox.url().as_str(),
+            "https://api.dev.strike.me/"
+        );
+
+        assert!(Environment::Production.is_production());
+        assert!(!Environment::Sandbox.is_production());
+        assert!("https://api.strike.me/v1"
+            .parse::<Environment>()
+            .unwrap()
+            .is_production());
+        assert!(!"http://localhost:3000"
+            .parse::<Environment>()
+            .unwrap()
+            .is_production());
+
+        assert_eq!(
+            "Sandbox".parse::<Environment>().unwrap(),
+            Environment::Sandbox
+        );
+        assert!("not a url".parse::<Environment>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_production_payments_denied() {
+        let strike = Strike::builder("api-key")
+            .deny_production_payments()
+            .build()
+            .unwrap();
+
+        let err = strike.pay_quote("quote-id").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::ProductionPaymentDenied)
+        ));
+
+        assert!(Strike::builder("api-key")
+            .environment(Environment::Sandbox)
+            .base_url("https://api.strike.me")
+            .build()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_payments_sent() {
+        for builder in [
+            Strike::builder("api-key"),
+            Strike::builder("api-key").deny_production_payments(),
+        ] {
+            let fake = FakeTransport::default();
+            let strike = builder
+                .environment(Environment::Sandbox)
+                .transport(fake.clone())
+                .build()
+                .unwrap();
+
+            // The fake answers `{}`, which is not a payment
+            let err = strike.pay_quote("quote-id").await.unwrap_err();
+            assert!(err.downcast_ref::<Error>().is_none());
+            assert_eq!(
+                fake.requests()[0].uri(),
+                "https://api.dev.strike.me/v1/payment-quotes/quote-id/execute"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_cannot_reach_production() {
+        let fake = FakeTransport::default();
+        let strike = Strike::builder("api-key")
+            .environment(Environment::Sandbox)
+            .transport(fake.clone())
+            .build()
+            .unwrap();
+
+        // Handle addresses resolve on production
+        let err = strike
+            .pay_handle(PayHandleQuoteRequest {
+                handle: "alice".to_string(),
+                amount: Amount {
+                    currency: Currency::BTC,
+                    amount: 0.0001,
+                },
+                source_currency: Currency::BTC,
+                description: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::ProductionPaymentDenied)
+        ));
+        assert!(fake.requests().is_empty());
+
+        // A custom url may proxy to production
+        let strike = Strike::builder("api-key")
+            .base_url("https://gw.internal/strike/")
+            .deny_production_payments()
+            .transport(fake.clone())
+            .build()
+            .unwrap();
+
+        let err = strike.pay_quote("quote-id").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::ProductionPaymentDenied)
+        ));
+        assert!(fake.requests().is_empty());
+    }
+}
@@ -9,6 +9,21 @@ pub enum Error {
     /// Invalid Url
     #[error("Invalid Url")]
     InvalidUrl,
+    /// Api key cannot be sent in a header
+    #[error("Invalid api key")]
+    InvalidApiKey,
+    /// Payment method is missing or has malformed fields
+    #[error("Invalid payment method: {0}")]
+    InvalidPaymentMethod(String),
+    /// Payment refused by the production payment guard
+    #[error("Payments against production are denied for this client")]
+    ProductionPaymentDenied,
+    /// Call is not available in the client's environment
+    #[error("Not supported in the {0} environment")]
+    UnsupportedEnvironment(crate::Environment),
+    /// From transport error
+    #[error(transparent)]
+    Transport(#[from] crate::TransportError),
     /// From reqwest error
     #[error(transparent)]
     ReqwestError(#[from] reqwest::Error),
@@ -0,0 +1,381 @@
+//! Strike Events
+//!
+//! Polling alternative to the webhook router for deployments that cannot
+//! receive callbacks from Strike.
+
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{append_list_query, timestamp, Filter, Page, Strike};
+
+/// Event data
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebHookData {
+    /// Entity Id
+    pub entity_id: String,
+    /// Changes
+    #[serde(default)]
+    pub changes: Vec<String>,
+}
+
+/// Strike event
+///
+/// Same shape whether delivered to a webhook or fetched from the events api
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEvent {
+    /// Event id
+    pub id: String,
+    /// Event type
+    pub event_type: String,
+    /// Webhook version
+    pub webhook_version: String,
+    /// Event data
+    pub data: WebHookData,
+    /// Created
+    pub created: String,
+    /// Delivery Success
+    pub delivery_success: Option<bool>,
+}
+
+/// Events query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventsQuery {
+    /// Only return events of these types
+    pub event_types: Vec<String>,
+    /// Only return events created at or after this time
+    pub created_from: Option<SystemTime>,
+    /// Only return events created before this time
+    pub created_before: Option<SystemTime>,
+    /// Number of events to skip
+    pub skip: Option<u32>,
+    /// Max number of events to return
+    pub top: Option<u32>,
+}
+
+impl EventsQuery {
+    fn filter(&self) -> Vec<String> {
+        Filter::default()
+            .any_eq("eventType", &self.event_types)
+            .created(self.created_from, self.created_before)
+            .into_clauses()
+    }
+}
+
+impl Strike {
+    /// List events
+    pub async fn get_events(&self, query: &EventsQuery) -> Result<Page<WebhookEvent>> {
+        let mut url = self.url(&["v1", "events"])?;
+        append_list_query(
+            &mut url,
+            &query.filter(),
+            "created asc",
+            query.skip,
+            query.top,
+        );
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on list events");
+                log::error!("{}", res);
+                bail!("Could not list events")
+            }
+        }
+    }
+
+    /// Get event by id
+    pub async fn get_event(&self, event_id: &str) -> Result<WebhookEvent> {
+        let url = self.url(&["v1", "events", event_id])?;
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on get event");
+                log::error!("{}", res);
+                bail!("Could not get event")
+            }
+        }
+    }
+}
+
+/// Event poller
+///
+/// Tracks a cursor over the events api and yields each event once, in the
+/// order they were created.
+#[derive(Debug, Clone)]
+pub struct EventPoller {
+    strike: Strike,
+    event_types: Vec<String>,
+    page_size: u32,
+    cursor: Option<String>,
+    seen_at_cursor: HashSet<String>,
+}
+
+impl EventPoller {
+    /// Default number of events requested per page
+    pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+    /// Create poller for event types
+    ///
+    /// Without a cursor the first poll returns all events Strike has retained.
+    pub fn new(strike: Strike, event_types: Vec<String>) -> Self {
+        Self {
+            strike,
+            event_types,
+            page_size: Self::DEFAULT_PAGE_SIZE,
+            cursor: None,
+            seen_at_cursor: HashSet::new(),
+        }
+    }
+
+    /// Poller for `invoice.updated` events, matching the invoice webhook
+    /// router
+    pub fn invoice_updates(strike: Strike) -> Self {
+        Self::new(strike, vec!["invoice.updated".to_string()])
+    }
+
+    /// Only yield events created at or after `created`, an RFC 3339
+    /// timestamp such as a persisted [`EventPoller::cursor`]
+    ///
+    /// Polls fail if `created` is not a valid timestamp.
+    pub fn starting_from(mut self, created: String) -> Self {
+        self.cursor = Some(created);
+        self.seen_at_cursor.clear();
+        self
+    }
+
+    /// Set page size
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Created timestamp of the last event yielded
+    ///
+    /// Persist this to resume polling with [`EventPoller::starting_from`]
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    /// Fetch events created since the last poll
+    pub async fn poll(&mut self) -> Result<Vec<WebhookEvent>> {
+        let mut events = Vec::new();
+        let mut skip = 0;
+
+        loop {
+            let query = EventsQuery {
+                event_types: self.event_types.clone(),
+                created_from: self.cursor.as_deref().map(timestamp::parse).transpose()?,
+                created_before: None,
+                skip: Some(skip),
+                top: Some(self.page_size),
+            };
+
+            let page = self.strike.get_events(&query).await?;
+            let fetched = page.items.len() as u32;
+
+            events.extend(page.items);
+
+            if fetched < self.page_size {
+                break;
+            }
+            skip += fetched;
+        }
+
+        // The filter is inclusive of the cursor so events sharing its
+        // timestamp are not lost, drop the ones already yielded
+        events.retain(|event| {
+            !(Some(&event.created) == self.cursor.as_ref()
+                && self.seen_at_cursor.contains(&event.id))
+        });
+
+        if let Some(last) = events.last() {
+            if Some(&last.created) != self.cursor.as_ref() {
+                self.cursor = Some(last.created.clone());
+                self.seen_at_cursor.clear();
+            }
+            let cursor = last.created.clone();
+            self.seen_at_cursor.extend(
+                events
+                    .iter()
+                    .filter(|event| event.created == cursor)
+                    .map(|event| event.id.clone()),
+            );
+        }
+
+        Ok(events)
+    }
+
+    /// Poll on an interval and send the entity id of each event on `sender`
+    ///
+    /// Delivers the same values as `Strike::create_invoice_webhook_router` so
+    /// either can feed the same receiver. Returns once the receiver is
+    /// dropped.
+    pub async fn run(
+        mut self,
+        sender: tokio::sync::mpsc::Sender<String>,
+        interval: Duration,
+    ) -> Result<()> {
+        loop {
+            match self.poll().await {
+                Ok(events) => {
+                    for event in events {
+                        log::debug!("Polled event update for: {}", event.data.entity_id);
+
+                        if sender.send(event.data.entity_id).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                Err(err) => log::warn!("Could not poll events: {}", err),
+            }
+
+            if sender.is_closed() {
+                return Ok(());
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn event(id: &str, second: u32) -> Value {
+        json!({
+            "id": id,
+            "eventType": "invoice.updated",
+            "webhookVersion": "v1",
+            "data": { "entityId": format!("entity-{id}"), "changes": ["state"] },
+            "created": format!("2024-01-01T00:00:0{second}+00:00"),
+            "deliverySuccess": true
+        })
+    }
+
+    fn page(events: &[Value]) -> Value {
+        json!({ "items": events, "count": events.len() })
+    }
+
+    fn ids(events: &[WebhookEvent]) -> Vec<&str> {
+        events.iter().map(|event| event.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_poll_pages_and_cursor() {
+        let fake = FakeTransport::default();
+        let mut poller = EventPoller::invoice_updates(fake.client()).page_size(2);
+
+        fake.respond_json(200, page(&[event("e1", 1), event("e2", 2)]))
+            .respond_json(200, page(&[event("e3", 2)]));
+
+        let events = poller.poll().await.unwrap();
+        assert_eq!(ids(&events), ["e1", "e2", "e3"]);
+        assert_eq!(poller.cursor(), Some("2024-01-01T00:00:02+00:00"));
+
+        let queries = fake.queries();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0]["$skip"], "0");
+        assert_eq!(queries[0]["$top"], "2");
+        assert_eq!(queries[1]["$skip"], "2");
+        assert_eq!(queries[0]["$filter"], "(eventType eq 'invoice.updated')");
+
+        // Events at the cursor were already yielded
+        fake.respond_json(200, page(&[event("e2", 2), event("e3", 2)]))
+            .respond_json(200, page(&[event("e4", 2), event("e5", 3)]))
+            .respond_json(200, page(&[]));
+
+        let events = poller.poll().await.unwrap();
+        assert_eq!(ids(&events), ["e4", "e5"]);
+        assert_eq!(poller.cursor(), Some("2024-01-01T00:00:03+00:00"));
+
+        let queries = fake.queries();
+        assert_eq!(
+            queries[2]["$filter"],
+            "(eventType eq 'invoice.updated') and created ge 2024-01-01T00:00:02+00:00"
+        );
+        assert_eq!(queries[4]["$skip"], "4");
+
+        // Nothing new keeps the cursor
+        fake.respond_json(200, page(&[event("e5", 3)]));
+        assert!(poller.poll().await.unwrap().is_empty());
+        assert_eq!(poller.cursor(), Some("2024-01-01T00:00:03+00:00"));
+    }
+
+    #[test]
+    fn test_filter_escapes_quotes() {
+        let query = EventsQuery {
+            event_types: vec!["a'b".to_string(), "invoice.created".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.filter(),
+            ["(eventType eq 'a''b' or eventType eq 'invoice.created')"]
+        );
+    }
+
+    #[test]
+    fn test_filter_created() {
+        let from = timestamp::parse("2024-01-01T00:00:02.4390395+00:00").unwrap();
+        let query = EventsQuery {
+            created_from: Some(from),
+            created_before: Some(from + Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.filter(),
+            [
+                "created ge 2024-01-01T00:00:02.4390395+00:00",
+                "created lt 2024-01-01T00:01:02.4390395+00:00"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_cursor() {
+        let fake = FakeTransport::default();
+        let mut poller =
+            EventPoller::invoice_updates(fake.client()).starting_from("x or 1 eq 1".to_string());
+
+        assert!(poller.poll().await.is_err());
+        assert!(fake.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_starting_from_resets_seen() {
+        let fake = FakeTransport::default();
+        let mut poller = EventPoller::invoice_updates(fake.client());
+
+        fake.respond_json(200, page(&[event("e1", 1)]));
+        assert_eq!(ids(&poller.poll().await.unwrap()), ["e1"]);
+
+        let mut poller = poller.starting_from("2024-01-01T00:00:01+00:00".to_string());
+        fake.respond_json(200, page(&[event("e1", 1)]));
+        assert_eq!(ids(&poller.poll().await.unwrap()), ["e1"]);
+    }
+
+    #[tokio::test]
+    async fn test_poll_error() {
+        let fake = FakeTransport::default();
+        let mut poller = EventPoller::invoice_updates(fake.client());
+
+        fake.respond(200, "<html></html>");
+        assert!(poller.poll().await.is_err());
+        assert_eq!(poller.cursor(), None);
+    }
+}
@@ -0,0 +1,271 @@
+//! Currency exchange
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{Amount, ConversionRate, Currency, Idempotent, QuotedConversion, Strike};
+
+/// Whether the fee is taken from or added to the amount
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FeePolicy {
+    /// Fee is taken from the amount
+    Inclusive,
+    /// Fee is added to the amount
+    Exclusive,
+}
+
+/// Exchange amount
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeAmount {
+    /// [`Amount`] to sell or buy
+    #[serde(flatten)]
+    pub amount: Amount,
+    /// Fee policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_policy: Option<FeePolicy>,
+}
+
+/// Currency exchange quote request
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyExchangeQuoteRequest {
+    /// Currency to sell
+    pub sell: Currency,
+    /// Currency to buy
+    pub buy: Currency,
+    /// Amount, in either the sell or buy currency
+    pub amount: ExchangeAmount,
+}
+
+/// Currency exchange state
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CurrencyExchangeState {
+    /// Quote created
+    New,
+    /// Exchange pending
+    Pending,
+    /// Exchange completed
+    Completed,
+    /// Exchange failed
+    Failed,
+}
+
+/// Currency exchange quote response
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyExchangeQuoteResponse {
+    /// Quote id
+    pub id: String,
+    /// Created timestamp
+    pub created: String,
+    /// Quote valid till
+    pub valid_until: String,
+    /// Amount sold
+    pub source: Amount,
+    /// Amount bought
+    pub target: Amount,
+    /// Fee
+    pub fee: Option<Amount>,
+    /// Conversion rate
+    pub conversion_rate: ConversionRate,
+    /// Exchange state
+    pub state: CurrencyExchangeState,
+    /// Completed timestamp
+    pub completed: Option<String>,
+}
+
+impl QuotedConversion for CurrencyExchangeQuoteResponse {
+    fn conversion_rate(&self) -> Option<&ConversionRate> {
+        Some(&self.conversion_rate)
+    }
+}
+
+impl Strike {
+    /// Create currency exchange quote
+    pub async fn create_currency_exchange_quote(
+        &self,
+        quote_request: CurrencyExchangeQuoteRequest,
+    ) -> Result<Idempotent<CurrencyExchangeQuoteResponse>> {
+        let url = self.url(&["v1", "currency-exchange-quotes"])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(
+                url,
+                Some(serde_json::to_value(quote_request)?),
+                &idempotency_key,
+            )
+            .await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on currency exchange quote");
+                log::error!("{}", res);
+                bail!("Could not create currency exchange quote")
+            }
+        }
+    }
+
+    /// Execute currency exchange quote
+    pub async fn execute_currency_exchange_quote(
+        &self,
+        quote_id: &str,
+    ) -> Result<Idempotent<CurrencyExchangeQuoteResponse>> {
+        let url = self.url(&["v1", "currency-exchange-quotes", quote_id, "execute"])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self.make_patch(url, &idempotency_key).await?;
+
+        // Strike accepts the execution without a body, errors are rejected
+        // by `make_patch`
+        if res.is_null() {
+            let res = self.get_currency_exchange_quote(quote_id).await?;
+            return Ok(Idempotent::new(idempotency_key, res));
+        }
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on currency exchange quote execution");
+                log::error!("{}", res);
+                bail!("Could not execute currency exchange quote")
+            }
+        }
+    }
+
+    /// Get currency exchange quote
+    pub async fn get_currency_exchange_quote(
+        &self,
+        quote_id: &str,
+    ) -> Result<CurrencyExchangeQuoteResponse> {
+        let url = self.url(&["v1", "currency-exchange-quotes", quote_id])?;
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on get currency exchange quote");
+                log::error!("{}", res);
+                bail!("Could not get currency exchange quote")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn quote(state: &str) -> serde_json::Value {
+        json!({
+            "id": "quote-1",
+            "created": "2024-01-01T00:00:00+00:00",
+            "validUntil": "2024-01-01T00:00:10+00:00",
+            "source": { "currency": "USD", "amount": "10.00" },
+            "target": { "currency": "BTC", "amount": "0.00020000" },
+            "fee": null,
+            "conversionRate": {
+                "amount": "50000.00",
+                "sourceCurrency": "BTC",
+                "targetCurrency": "USD"
+            },
+            "state": state,
+            "completed": null
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_quote() {
+        let fake = FakeTransport::default();
+        fake.respond_json(201, quote("NEW"));
+
+        let res = fake
+            .client()
+            .create_currency_exchange_quote(CurrencyExchangeQuoteRequest {
+                sell: Currency::USD,
+                buy: Currency::BTC,
+                amount: ExchangeAmount {
+                    amount: Amount {
+                        currency: Currency::USD,
+                        amount: 10.0,
+                    },
+                    fee_policy: None,
+                },
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(res.id, "quote-1");
+        assert_eq!(res.state, CurrencyExchangeState::New);
+        assert_eq!(fake.calls(), ["POST /v1/currency-exchange-quotes"]);
+        assert_eq!(
+            fake.bodies(),
+            [json!({
+                "sell": "USD",
+                "buy": "BTC",
+                "amount": { "currency": "USD", "amount": 10.0 }
+            })]
+        );
+
+        let request = &fake.requests()[0];
+        assert_eq!(
+            request.headers()[crate::retry::IDEMPOTENCY_KEY_HEADER],
+            res.idempotency_key.as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_without_body_fetches_quote() {
+        let fake = FakeTransport::default();
+        fake.respond(200, "").respond_json(200, quote("COMPLETED"));
+
+        let res = fake
+            .client()
+            .execute_currency_exchange_quote("quote-1")
+            .await
+            .unwrap();
+
+        assert_eq!(res.state, CurrencyExchangeState::Completed);
+        assert_eq!(
+            fake.calls(),
+            [
+                "PATCH /v1/currency-exchange-quotes/quote-1/execute",
+                "GET /v1/currency-exchange-quotes/quote-1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_error_is_not_success() {
+        let fake = FakeTransport::default();
+        fake.respond(401, "");
+
+        assert!(fake
+            .client()
+            .execute_currency_exchange_quote("quote-1")
+            .await
+            .is_err());
+        assert_eq!(fake.calls().len(), 1);
+
+        // Executions are not retried on a proxy error page
+        let fake = FakeTransport::default();
+        fake.respond(503, "<html>Bad gateway</html>");
+
+        assert!(fake
+            .client()
+            .execute_currency_exchange_quote("quote-1")
+            .await
+            .is_err());
+        assert_eq!(fake.calls().len(), 1);
+    }
+}
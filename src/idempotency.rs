@@ -0,0 +1,161 @@
+//! Idempotency keys
+//!
+//! Every mutating request is sent with an [`IDEMPOTENCY_KEY_HEADER`]. Strike
+//! acts on a key at most once, so a request that failed without a response
+//! can be sent again with the same key without creating a second invoice or
+//! paying twice.
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(doc)]
+use crate::retry::IDEMPOTENCY_KEY_HEADER;
+use crate::Strike;
+
+/// Idempotency key
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    /// Idempotency key from an existing value
+    pub fn new<S>(key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(key.into())
+    }
+
+    /// Random UUID v4 key
+    pub fn generate() -> Self {
+        let mut bytes: [u8; 16] = rand::random();
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+        Self(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[0..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..32]
+        ))
+    }
+
+    /// Key as str
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for IdempotencyKey {
+    fn from(key: String) -> Self {
+        Self(key)
+    }
+}
+
+impl From<&str> for IdempotencyKey {
+    fn from(key: &str) -> Self {
+        Self(key.to_string())
+    }
+}
+
+/// Response of a mutating call with the [`IdempotencyKey`] it was sent with
+///
+/// Derefs to the response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Idempotent<T> {
+    /// Key the request was sent with
+    pub idempotency_key: IdempotencyKey,
+    /// Response
+    pub value: T,
+}
+
+impl<T> Idempotent<T> {
+    pub(crate) fn new(idempotency_key: IdempotencyKey, value: T) -> Self {
+        Self {
+            idempotency_key,
+            value,
+        }
+    }
+
+    /// Response without the key
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Idempotent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl Strike {
+    /// Client that sends `idempotency_key` with its next mutating call
+    ///
+    /// Use it to repeat a call such as [`Strike::pay_quote`] that failed
+    /// without a response, with the key from the first attempt. Later calls,
+    /// also through clones of the returned client, get generated keys.
+    pub fn with_idempotency_key<K>(&self, idempotency_key: K) -> Self
+    where
+        K: Into<IdempotencyKey>,
+    {
+        Self {
+            idempotency_key: Arc::new(Mutex::new(Some(idempotency_key.into()))),
+            ..self.clone()
+        }
+    }
+
+    // Supplied key the first time it is asked for, then a new key each call
+    pub(crate) fn idempotency_key(&self) -> IdempotencyKey {
+        let supplied = self
+            .idempotency_key
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+
+        supplied.unwrap_or_else(IdempotencyKey::generate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let key = IdempotencyKey::generate();
+        let parts: Vec<&str> = key.as_str().split('-').collect();
+
+        assert_eq!(
+            parts.iter().map(|p| p.len()).collect::<Vec<_>>(),
+            [8, 4, 4, 4, 12]
+        );
+        assert!(parts[2].starts_with('4'));
+        assert_ne!(key, IdempotencyKey::generate());
+    }
+
+    #[test]
+    fn test_supplied_key_used_once() {
+        let strike = Strike::new("api-key", None)
+            .unwrap()
+            .with_idempotency_key("key-1");
+
+        assert_eq!(strike.idempotency_key().as_str(), "key-1");
+        assert_ne!(strike.idempotency_key().as_str(), "key-1");
+    }
+}
@@ -3,7 +3,7 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::{Amount, ConversionRate, InvoiceState, Strike};
+use crate::{Amount, ConversionRate, Idempotent, InvoiceState, QuotedConversion, Strike};
 
 /// Invoice Request
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -33,7 +33,8 @@ pub struct InvoiceResponse {
     pub description: Option<String>,
     /// Isser ID
     pub issuer_id: String,
-    /// Receiver ID
+    /// Receiver ID, differs from the issuer for invoices created with
+    /// [`Strike::create_invoice_for_handle`]
     pub receiver_id: String,
 }
 
@@ -61,17 +62,32 @@ pub struct InvoiceQuoteResponse {
     pub conversion_rate: ConversionRate,
 }
 
+impl QuotedConversion for InvoiceQuoteResponse {
+    fn conversion_rate(&self) -> Option<&ConversionRate> {
+        Some(&self.conversion_rate)
+    }
+}
+
 impl Strike {
     /// Create Invoice
-    pub async fn create_invoice(&self, invoice_request: InvoiceRequest) -> Result<InvoiceResponse> {
-        let url = self.base_url.join("/v1/invoices")?;
+    pub async fn create_invoice(
+        &self,
+        invoice_request: InvoiceRequest,
+    ) -> Result<Idempotent<InvoiceResponse>> {
+        let url = self.url(&["v1", "invoices"])?;
+
+        let idempotency_key = self.idempotency_key();
 
         let res = self
-            .make_post(url, Some(serde_json::to_value(invoice_request)?))
+            .make_post(
+                url,
+                Some(serde_json::to_value(invoice_request)?),
+                &idempotency_key,
+            )
             .await?;
 
         match serde_json::from_value(res.clone()) {
-            Ok(res) => Ok(res),
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
             Err(_) => {
                 log::error!("Api error response on invoice creation");
                 log::error!("{}", res);
@@ -80,9 +96,41 @@ impl Strike {
         }
     }
 
+    /// Create Invoice for another account's handle
+    ///
+    /// The invoice credits the account with `handle`, which is returned as
+    /// [`InvoiceResponse::receiver_id`] while the api key's account is the
+    /// [`InvoiceResponse::issuer_id`]
+    pub async fn create_invoice_for_handle(
+        &self,
+        handle: &str,
+        invoice_request: InvoiceRequest,
+    ) -> Result<Idempotent<InvoiceResponse>> {
+        let url = self.url(&["v1", "invoices", "handle", handle])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(
+                url,
+                Some(serde_json::to_value(invoice_request)?),
+                &idempotency_key,
+            )
+            .await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on invoice creation for handle");
+                log::error!("{}", res);
+                bail!("Could not create invoice for handle")
+            }
+        }
+    }
+
     /// Find incoming invoice
     pub async fn get_incoming_invoice(&self, invoice_id: &str) -> Result<InvoiceResponse> {
-        let url = self.base_url.join("/v1/invoices/")?.join(invoice_id)?;
+        let url = self.url(&["v1", "invoices", invoice_id])?;
 
         let res = self.make_get(url).await?;
 
@@ -97,15 +145,20 @@ impl Strike {
     }
 
     /// Invoice quote
-    pub async fn invoice_quote(&self, invoice_id: &str) -> Result<InvoiceQuoteResponse> {
-        let url = self
-            .base_url
-            .join(&format!("/v1/invoices/{invoice_id}/quote"))?;
+    pub async fn invoice_quote(
+        &self,
+        invoice_id: &str,
+    ) -> Result<Idempotent<InvoiceQuoteResponse>> {
+        let url = self.url(&["v1", "invoices", invoice_id, "quote"])?;
 
-        let res = self.make_post(url, None::<String>).await?;
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(url, None::<String>, &idempotency_key)
+            .await?;
 
         match serde_json::from_value(res.clone()) {
-            Ok(res) => Ok(res),
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
             Err(_) => {
                 log::error!("Api error response on invoice quote");
                 log::error!("{}", res);
@@ -114,3 +167,51 @@ impl Strike {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+    use crate::Currency;
+
+    #[tokio::test]
+    async fn test_create_invoice_for_handle() {
+        let fake = FakeTransport::default();
+        fake.respond_json(
+            201,
+            json!({
+                "invoiceId": "invoice-1",
+                "amount": { "currency": "USD", "amount": "1.00" },
+                "state": "UNPAID",
+                "created": "2024-01-01T00:00:00+00:00",
+                "description": "coffee",
+                "issuerId": "issuer",
+                "receiverId": "receiver"
+            }),
+        );
+
+        let invoice = fake
+            .client()
+            .create_invoice_for_handle(
+                "alice",
+                InvoiceRequest {
+                    correlation_id: None,
+                    description: Some("coffee".to_string()),
+                    amount: Amount {
+                        currency: Currency::USD,
+                        amount: 1.0,
+                    },
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(invoice.issuer_id, "issuer");
+        assert_eq!(invoice.receiver_id, "receiver");
+        assert_eq!(invoice.state, InvoiceState::Unpaid);
+        assert_eq!(fake.calls(), ["POST /v1/invoices/handle/alice"]);
+        assert_eq!(fake.bodies()[0]["description"], "coffee");
+    }
+}
@@ -5,32 +5,83 @@
 #![warn(rustdoc::bare_urls)]
 
 use std::fmt;
-use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
-use anyhow::{anyhow, bail};
-use rand::distributions::Alphanumeric;
-use rand::Rng;
-use reqwest::{Client, IntoUrl, Url};
+use anyhow::bail;
+use http::header::AUTHORIZATION;
+use http::HeaderValue;
+use rate_limit::Throttle;
+use reqwest::{Method, StatusCode, Url};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use zeroize::Zeroizing;
 
+pub mod account;
+pub mod builder;
+pub mod conversion;
+pub mod deposits;
+pub mod environment;
 mod error;
+pub mod events;
+pub mod exchange;
+#[cfg(feature = "webhooks")]
 pub(crate) mod hex;
+pub mod idempotency;
 pub mod invoice;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod pay_ln;
+pub mod payment_methods;
+pub mod payments;
+pub mod payouts;
+pub mod rate_limit;
+pub mod rates;
+pub mod retry;
+pub mod secret;
+pub mod telemetry;
+pub(crate) mod timestamp;
+pub mod transport;
+#[cfg(feature = "webhooks")]
 pub mod webhooks;
 
+pub use account::{AccountCurrency, AccountProfile};
+pub use builder::StrikeBuilder;
+pub use conversion::{QuotedConversion, Rounding};
+pub use deposits::*;
+pub use environment::Environment;
 pub use error::Error;
+pub use events::{EventPoller, EventsQuery, WebhookEvent};
+pub use exchange::*;
+pub use idempotency::{IdempotencyKey, Idempotent};
 pub use invoice::*;
 pub use pay_ln::*;
+pub use payment_methods::*;
+pub use payments::*;
+pub use payouts::*;
+pub use rate_limit::RateLimit;
+pub use rates::RatesCache;
+pub use retry::RetryPolicy;
+pub use secret::Secret;
+pub use transport::{ReqwestTransport, Transport, TransportError};
 
 /// Strike
 #[derive(Debug, Clone)]
 pub struct Strike {
-    api_key: String,
+    api_key: Secret,
     base_url: Url,
-    client: Client,
-    webhook_secret: String,
+    environment: Environment,
+    deny_production_payments: bool,
+    transport: Arc<dyn Transport>,
+    #[cfg(feature = "webhooks")]
+    webhook_secret: Secret,
+    retry_policy: RetryPolicy,
+    // Retry executions of payments, payouts and exchanges
+    retry_executions: bool,
+    // Supplied key for the next mutating call
+    idempotency_key: Arc<Mutex<Option<IdempotencyKey>>>,
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+    throttle: Option<Arc<Throttle>>,
 }
 
 /// Currency unit
@@ -41,6 +92,12 @@ pub enum Currency {
     USD,
     /// EURO
     EUR,
+    /// British Pound
+    GBP,
+    /// Australian Dollar
+    AUD,
+    /// Tether
+    USDT,
     /// Bitcoin
     BTC,
 }
@@ -50,6 +107,9 @@ impl fmt::Display for Currency {
         match self {
             Self::USD => write!(f, "USD"),
             Self::EUR => write!(f, "EUR"),
+            Self::GBP => write!(f, "GBP"),
+            Self::AUD => write!(f, "AUD"),
+            Self::USDT => write!(f, "USDT"),
             Self::BTC => write!(f, "BTC"),
         }
     }
@@ -83,6 +143,8 @@ impl Amount {
     }
 
     /// Unit as sats
+    ///
+    /// Use [`Amount::to_sats_with_rate`] for fiat amounts
     pub fn to_sats(&self) -> anyhow::Result<u64> {
         match self.currency {
             Currency::BTC => Ok((self.amount * 100_000_000.0) as u64),
@@ -106,10 +168,15 @@ pub enum InvoiceState {
 }
 
 /// Conversion rate for quote
+///
+/// `amount` is the price of one `source_currency` in `target_currency`, the
+/// direction [`Amount::convert`] reads it in. Rates between BTC and fiat are
+/// the price of one BTC, and are turned around when deserialised if Strike
+/// labels them from the fiat side.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "QuotedRate")]
 pub struct ConversionRate {
     /// Amount
-    #[serde(deserialize_with = "parse_f64_from_string")]
     pub amount: f64,
     /// Source Unit
     #[serde(rename = "sourceCurrency")]
@@ -119,6 +186,122 @@ pub struct ConversionRate {
     pub target_currency: Currency,
 }
 
+// Conversion rate as Strike sends it
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotedRate {
+    #[serde(deserialize_with = "parse_f64_from_string")]
+    amount: f64,
+    source_currency: Currency,
+    target_currency: Currency,
+}
+
+impl From<QuotedRate> for ConversionRate {
+    fn from(rate: QuotedRate) -> Self {
+        // The price of one fiat unit in BTC is below one, a fiat to BTC rate
+        // above it is the price of one BTC
+        if rate.target_currency == Currency::BTC
+            && rate.source_currency != Currency::BTC
+            && rate.amount > 1.0
+        {
+            return Self {
+                amount: rate.amount,
+                source_currency: Currency::BTC,
+                target_currency: rate.source_currency,
+            };
+        }
+
+        Self {
+            amount: rate.amount,
+            source_currency: rate.source_currency,
+            target_currency: rate.target_currency,
+        }
+    }
+}
+
+/// Page of items from a list endpoint
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Page<T> {
+    /// Items
+    pub items: Vec<T>,
+    /// Total number of items matching the query
+    pub count: u64,
+}
+
+// OData string literal of `value`, quotes are escaped by doubling them
+fn odata_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+// OData `$filter` clauses for `append_list_query`, values are quoted and
+// timestamps formatted here so nothing is pasted into the filter as is
+#[derive(Debug, Default)]
+pub(crate) struct Filter {
+    clauses: Vec<String>,
+}
+
+impl Filter {
+    // `field eq 'value'`, left out without a value
+    pub(crate) fn eq(mut self, field: &str, value: Option<&str>) -> Self {
+        if let Some(value) = value {
+            self.clauses
+                .push(format!("{field} eq {}", odata_string(value)));
+        }
+        self
+    }
+
+    // `field` equal to any of `values`, left out when empty
+    pub(crate) fn any_eq(mut self, field: &str, values: &[String]) -> Self {
+        if !values.is_empty() {
+            let clauses: Vec<String> = values
+                .iter()
+                .map(|value| format!("{field} eq {}", odata_string(value)))
+                .collect();
+            self.clauses.push(format!("({})", clauses.join(" or ")));
+        }
+        self
+    }
+
+    // Created at or after `from` and before `before`
+    pub(crate) fn created(mut self, from: Option<SystemTime>, before: Option<SystemTime>) -> Self {
+        if let Some(from) = from {
+            self.clauses
+                .push(format!("created ge {}", timestamp::format_precise(from)));
+        }
+        if let Some(before) = before {
+            self.clauses
+                .push(format!("created lt {}", timestamp::format_precise(before)));
+        }
+        self
+    }
+
+    pub(crate) fn into_clauses(self) -> Vec<String> {
+        self.clauses
+    }
+}
+
+// Append OData list parameters, `filter` clauses are joined with `and`
+pub(crate) fn append_list_query(
+    url: &mut Url,
+    filter: &[String],
+    orderby: &str,
+    skip: Option<u32>,
+    top: Option<u32>,
+) {
+    let mut pairs = url.query_pairs_mut();
+
+    if !filter.is_empty() {
+        pairs.append_pair("$filter", &filter.join(" and "));
+    }
+    pairs.append_pair("$orderby", orderby);
+    if let Some(skip) = skip {
+        pairs.append_pair("$skip", &skip.to_string());
+    }
+    if let Some(top) = top {
+        pairs.append_pair("$top", &top.to_string());
+    }
+}
+
 impl Strike {
     /// Create Strike client
     /// # Arguments
@@ -131,104 +314,242 @@ impl Strike {
     /// let client = Strike::new("xxxxxxxxxxx", None).unwrap();
     /// ```
     pub fn new(api_key: &str, api_url: Option<String>) -> anyhow::Result<Self> {
-        let base_url = match api_url {
-            Some(url) => Url::from_str(&url)?,
-            None => Url::from_str("https://api.strike.me")?,
-        };
+        let mut builder = StrikeBuilder::new(api_key);
+        if let Some(url) = api_url {
+            builder = builder.base_url(&url);
+        }
 
-        let client = reqwest::Client::builder().build()?;
-        let secret: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(15)
-            .map(char::from)
-            .collect();
+        builder.build()
+    }
 
-        Ok(Self {
-            api_key: api_key.to_string(),
-            base_url,
-            client,
-            webhook_secret: secret,
-        })
+    /// Configure Strike client with [`StrikeBuilder`]
+    pub fn builder(api_key: &str) -> StrikeBuilder {
+        StrikeBuilder::new(api_key)
     }
 
-    async fn make_get<U>(&self, url: U) -> Result<Value, Error>
-    where
-        U: IntoUrl,
-    {
-        let res = self
-            .client
-            .get(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .header("accept", "application/json")
-            .send()
+    /// [`Environment`] of client
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    /// [`RetryPolicy`] of client
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    // Api url of `segments` under the base url, keeping any base path and
+    // percent-encoding each segment
+    fn url(&self, segments: &[&str]) -> Result<Url, Error> {
+        // Dot segments would be dropped or resolved rather than encoded
+        if segments
+            .iter()
+            .any(|segment| segment.is_empty() || *segment == "." || *segment == "..")
+        {
+            return Err(Error::InvalidUrl);
+        }
+
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| Error::InvalidUrl)?
+            .pop_if_empty()
+            .extend(segments);
+
+        Ok(url)
+    }
+
+    // Request to `url` with the auth header
+    fn request(&self, method: Method, url: Url) -> Result<http::request::Builder, Error> {
+        // Sized up front so no copy of the key is left behind by a realloc
+        let api_key = self.api_key.expose();
+        let mut bearer = Zeroizing::new(String::with_capacity("Bearer ".len() + api_key.len()));
+        bearer.push_str("Bearer ");
+        bearer.push_str(api_key);
+
+        let mut authorization = HeaderValue::from_str(&bearer).map_err(|_| Error::InvalidApiKey)?;
+
+        // Sensitive values are hidden from the header's `Debug`
+        authorization.set_sensitive(true);
+
+        Ok(http::Request::builder()
+            .method(method)
+            .uri(url.as_str())
+            .header(AUTHORIZATION, authorization))
+    }
+
+    // Send request, retrying per the retry policy if it is safe to repeat
+    async fn send(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Vec<u8>>, TransportError> {
+        let call = telemetry::Call::start(&request, self.base_url.path());
+
+        let (res, attempts) = call
+            .instrument(self.send_with_retries(request, &call))
             .await;
+        call.finish(attempts, &res);
+
+        res
+    }
+
+    // Response and number of attempts
+    async fn send_with_retries(
+        &self,
+        request: http::Request<Vec<u8>>,
+        call: &telemetry::Call,
+    ) -> (Result<http::Response<Vec<u8>>, TransportError>, u32) {
+        let idempotent = retry::is_idempotent(&request) || self.retry_executions;
+
+        let mut attempt = 1;
+        loop {
+            if attempt >= self.retry_policy.max_attempts {
+                return (self.execute(request, call).await, attempt);
+            }
+
+            let backoff = self.retry_policy.backoff(attempt);
+
+            let wait = match self.execute(transport::clone_request(&request), call).await {
+                // A `429` was not acted on, so any request can be sent again
+                Ok(res)
+                    if res.status() == StatusCode::TOO_MANY_REQUESTS
+                        || (idempotent && retry::is_retryable_status(res.status())) =>
+                {
+                    // Strike's `Retry-After` takes precedence when it is longer,
+                    // a wait beyond the max backoff is left to the caller
+                    let wait = match rate_limit::retry_after(res.headers()) {
+                        Some(retry_after) if retry_after > self.retry_policy.max_backoff => {
+                            return (Ok(res), attempt)
+                        }
+                        Some(retry_after) => backoff.max(retry_after),
+                        None => backoff,
+                    };
+
+                    log::warn!(
+                        "{} {} returned {}, retrying",
+                        request.method(),
+                        request.uri().path(),
+                        res.status()
+                    );
+
+                    wait
+                }
+                Err(err) if idempotent && err.is_retryable() => {
+                    log::warn!(
+                        "{} {} failed, retrying: {}",
+                        request.method(),
+                        request.uri().path(),
+                        err
+                    );
+                    backoff
+                }
+                res => return (res, attempt),
+            };
 
-        match res {
-            Ok(res) => Ok(res.json::<Value>().await.unwrap_or_default()),
-            Err(err) => Err(err.into()),
+            tokio::time::sleep(wait).await;
+            attempt += 1;
         }
     }
 
-    async fn make_post<U, T>(&self, url: U, data: Option<T>) -> anyhow::Result<Value>
+    // Send single attempt, waiting on the throttle and recording the quota
+    async fn execute(
+        &self,
+        request: http::Request<Vec<u8>>,
+        call: &telemetry::Call,
+    ) -> Result<http::Response<Vec<u8>>, TransportError> {
+        if let Some(throttle) = &self.throttle {
+            throttle.acquire().await;
+        }
+
+        call.attempt(&request);
+
+        let res = self.transport.send(request).await?;
+        self.record_rate_limit(res.headers());
+
+        Ok(res)
+    }
+
+    async fn make_get(&self, url: Url) -> Result<Value, Error> {
+        let res = self.get_response(url).await?;
+
+        Ok(serde_json::from_slice(res.body()).unwrap_or_default())
+    }
+
+    // Response to a get, for callers that need the status
+    async fn get_response(&self, url: Url) -> Result<http::Response<Vec<u8>>, Error> {
+        let request = self
+            .request(Method::GET, url)?
+            .header("Content-Type", "application/json")
+            .header("accept", "application/json")
+            .body(Vec::new())
+            .map_err(TransportError::from)?;
+
+        Ok(self.send(request).await?)
+    }
+
+    async fn make_post<T>(
+        &self,
+        url: Url,
+        data: Option<T>,
+        idempotency_key: &IdempotencyKey,
+    ) -> anyhow::Result<Value>
     where
-        U: IntoUrl,
         T: Serialize,
     {
-        let value = match data {
-            Some(data) => {
-                self.client
-                    .post(url)
-                    .header("Authorization", format!("Bearer {}", self.api_key))
-                    .header("Content-Type", "application/json")
-                    .header("accept", "application/json")
-                    .json(&data)
-                    .send()
-                    .await?
-                    .json::<Value>()
-                    .await?
-            }
-            None => {
-                self.client
-                    .post(url)
-                    .header("Authorization", format!("Bearer {}", self.api_key))
-                    .header("Content-Length", "0")
-                    .header("accept", "application/json")
-                    .send()
-                    .await?
-                    .json::<Value>()
-                    .await?
-            }
+        let request = self
+            .request(Method::POST, url)?
+            .header("accept", "application/json")
+            .header(retry::IDEMPOTENCY_KEY_HEADER, idempotency_key.as_str());
+
+        let request = match data {
+            Some(data) => request
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_vec(&data)?)?,
+            None => request.header("Content-Length", "0").body(Vec::new())?,
         };
-        Ok(value)
+
+        let res = self.send(request).await?;
+
+        Ok(serde_json::from_slice(res.body())?)
     }
 
-    async fn make_patch<U>(&self, url: U) -> anyhow::Result<Value>
-    where
-        U: IntoUrl,
-    {
-        Ok(self
-            .client
-            .patch(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
+    async fn make_patch(
+        &self,
+        url: Url,
+        idempotency_key: &IdempotencyKey,
+    ) -> anyhow::Result<Value> {
+        // Every patch executes a payment, exchange or payout, only the
+        // sandbox is known not to reach production
+        if self.deny_production_payments && !environment::is_sandbox_url(&url) {
+            bail!(Error::ProductionPaymentDenied);
+        }
+
+        let request = self
+            .request(Method::PATCH, url)?
             .header("Content-Length", "0")
             .header("accept", "application/json")
-            .send()
-            .await?
-            .json::<Value>()
-            .await?)
+            .header(retry::IDEMPOTENCY_KEY_HEADER, idempotency_key.as_str())
+            .body(Vec::new())?;
+
+        let res = self.send(request).await?;
+
+        // Callers treat an empty body as success, so errors must not get here
+        if !res.status().is_success() {
+            log::error!(
+                "Api error response on patch: {}",
+                String::from_utf8_lossy(res.body())
+            );
+            bail!("Strike responded {}", res.status());
+        }
+
+        Ok(serde_json::from_slice(res.body()).unwrap_or_default())
     }
 
-    async fn make_delete<U>(&self, url: U) -> anyhow::Result<()>
-    where
-        U: IntoUrl,
-    {
-        self.client
-            .delete(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
+    #[cfg(feature = "webhooks")]
+    async fn make_delete(&self, url: Url) -> anyhow::Result<()> {
+        let request = self.request(Method::DELETE, url)?.body(Vec::new())?;
+
+        self.send(request)
             .await
-            .map_err(|err| anyhow!("Error making delete: {}", err.to_string()))?;
+            .map_err(|err| anyhow::anyhow!("Error making delete: {}", err))?;
 
         Ok(())
     }
@@ -250,3 +571,131 @@ impl Strike {
 
     */
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    // Client behind a path prefix
+    fn prefixed_client() -> (Strike, FakeTransport) {
+        let fake = FakeTransport::default();
+
+        let strike = Strike::builder("api-key")
+            .base_url("https://gw.internal/strike/")
+            .transport(fake.clone())
+            .build()
+            .unwrap();
+
+        (strike, fake)
+    }
+
+    fn last_uri(fake: &FakeTransport) -> String {
+        fake.requests().last().unwrap().uri().to_string()
+    }
+
+    #[test]
+    fn test_odata_string() {
+        assert_eq!(odata_string("invoice.updated"), "'invoice.updated'");
+        assert_eq!(odata_string("x' or 1 eq 1 or 'a"), "'x'' or 1 eq 1 or ''a'");
+    }
+
+    #[test]
+    fn test_url_keeps_base_path() {
+        let strike = Strike::new("api-key", None).unwrap();
+        assert_eq!(
+            strike.url(&["v1", "invoices"]).unwrap().as_str(),
+            "https://api.strike.me/v1/invoices"
+        );
+
+        for base_url in ["https://gw.internal/strike", "https://gw.internal/strike/"] {
+            let strike = Strike::new("api-key", Some(base_url.to_string())).unwrap();
+            assert_eq!(
+                strike.url(&["v1", "invoices", "a/b c"]).unwrap().as_str(),
+                "https://gw.internal/strike/v1/invoices/a%2Fb%20c"
+            );
+        }
+
+        for id in ["", ".", ".."] {
+            assert!(matches!(
+                strike.url(&["v1", "invoices", id]),
+                Err(Error::InvalidUrl)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_api_key_is_not_sent() {
+        let fake = FakeTransport::default();
+        let strike = Strike::builder("api\nkey")
+            .transport(fake.clone())
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            strike.get_outgoing_payment("id").await,
+            Err(Error::InvalidApiKey)
+        ));
+        assert!(fake.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_incoming_invoice_url() {
+        let (strike, fake) = prefixed_client();
+
+        strike.get_incoming_invoice("../balances").await.ok();
+        assert_eq!(
+            last_uri(&fake),
+            "https://gw.internal/strike/v1/invoices/..%2Fbalances"
+        );
+
+        assert!(strike.get_incoming_invoice("..").await.is_err());
+        assert!(strike.get_incoming_invoice("").await.is_err());
+        assert_eq!(fake.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invoice_quote_url() {
+        let (strike, fake) = prefixed_client();
+
+        strike.invoice_quote("id?x=1#y").await.ok();
+        assert_eq!(
+            last_uri(&fake),
+            "https://gw.internal/strike/v1/invoices/id%3Fx=1%23y/quote"
+        );
+
+        assert!(strike.invoice_quote("").await.is_err());
+        assert!(strike.invoice_quote(".").await.is_err());
+        assert_eq!(fake.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pay_quote_url() {
+        let (strike, fake) = prefixed_client();
+
+        strike.pay_quote("quote/1").await.ok();
+        assert_eq!(
+            last_uri(&fake),
+            "https://gw.internal/strike/v1/payment-quotes/quote%2F1/execute"
+        );
+
+        assert!(strike.pay_quote("..").await.is_err());
+        assert_eq!(fake.requests().len(), 1);
+    }
+
+    #[cfg(feature = "webhooks")]
+    #[tokio::test]
+    async fn test_delete_subscription_url() {
+        let (strike, fake) = prefixed_client();
+
+        strike.delete_subscription("sub 1").await.unwrap();
+        assert_eq!(
+            last_uri(&fake),
+            "https://gw.internal/strike/v1/subscriptions/sub%201"
+        );
+
+        assert!(strike.delete_subscription("..").await.is_err());
+        assert!(strike.delete_subscription("").await.is_err());
+        assert_eq!(fake.requests().len(), 1);
+    }
+}
@@ -0,0 +1,851 @@
+//! Mock Strike server
+//!
+//! In-process server implementing invoices, quotes, payments and webhook
+//! subscriptions, for deterministic tests without an api key or network.
+//! State changes made with [`MockStrike`] are delivered as signed webhooks to
+//! subscribed urls before the call returns.
+//!
+//! Paying the `ln_invoice` of a mock invoice quote completes that invoice, so
+//! both sides of a payment can be tested against one server.
+//!
+//! # Example
+//! ```
+//! use strike_rs::mock::MockStrike;
+//! use strike_rs::{Amount, Currency, InvoiceRequest, InvoiceState};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mock = MockStrike::start().await?;
+//! let strike = mock.client()?;
+//!
+//! let invoice = strike
+//!     .create_invoice(InvoiceRequest {
+//!         correlation_id: None,
+//!         description: None,
+//!         amount: Amount {
+//!             currency: Currency::USD,
+//!             amount: 10.0,
+//!         },
+//!     })
+//!     .await?;
+//!
+//! mock.pay_invoice(&invoice.invoice_id).await?;
+//!
+//! let invoice = strike.get_incoming_invoice(&invoice.invoice_id).await?;
+//! assert_eq!(invoice.state, InvoiceState::Paid);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+use axum::body::{self, Bytes, Full};
+use axum::extract::{Path, State};
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{Method, Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, patch, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use crate::retry::IDEMPOTENCY_KEY_HEADER;
+use crate::webhooks::{sign_webhook, WebHookData, WebhookEvent, SIGNATURE_HEADER};
+use crate::{
+    timestamp, Currency, IdempotencyKey, InvoiceResponse, InvoiceState, RetryPolicy, Strike,
+};
+
+/// Api key the mock server accepts
+pub const MOCK_API_KEY: &str = "mock-api-key";
+
+/// Account id of the api key's account
+pub const MOCK_ACCOUNT_ID: &str = "mock-account";
+
+/// Mock Strike server
+///
+/// Shuts down when dropped
+#[derive(Debug)]
+pub struct MockStrike {
+    addr: SocketAddr,
+    shared: Shared,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Debug, Clone)]
+struct Shared {
+    state: Arc<Mutex<MockState>>,
+    client: reqwest::Client,
+}
+
+#[derive(Debug)]
+struct MockState {
+    btc_rate: f64,
+    payment_state: InvoiceState,
+    failures: Vec<StatusCode>,
+    invoices: HashMap<String, Value>,
+    // Bolt11 of an invoice quote to the invoice id
+    ln_invoices: HashMap<String, String>,
+    payment_quotes: HashMap<String, Value>,
+    // Payment quote id to the mock invoice it pays
+    quote_invoices: HashMap<String, String>,
+    executed_quotes: HashMap<String, String>,
+    payments: HashMap<String, Value>,
+    subscriptions: Vec<Subscription>,
+    events: Vec<WebhookEvent>,
+    idempotent: HashMap<String, (StatusCode, Bytes)>,
+}
+
+#[derive(Debug, Clone)]
+struct Subscription {
+    id: String,
+    webhook_url: String,
+    webhook_version: String,
+    secret: String,
+    enabled: bool,
+    event_types: Vec<String>,
+    created: String,
+}
+
+impl Subscription {
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "webhookUrl": self.webhook_url,
+            "webhookVersion": self.webhook_version,
+            "enabled": self.enabled,
+            "eventTypes": self.event_types,
+            "created": self.created,
+        })
+    }
+}
+
+impl MockStrike {
+    /// Start mock server on a random local port
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let shared = Shared {
+            state: Arc::new(Mutex::new(MockState {
+                btc_rate: 50_000.0,
+                payment_state: InvoiceState::Completed,
+                failures: Vec::new(),
+                invoices: HashMap::new(),
+                ln_invoices: HashMap::new(),
+                payment_quotes: HashMap::new(),
+                quote_invoices: HashMap::new(),
+                executed_quotes: HashMap::new(),
+                payments: HashMap::new(),
+                subscriptions: Vec::new(),
+                events: Vec::new(),
+                idempotent: HashMap::new(),
+            })),
+            client: reqwest::Client::new(),
+        };
+
+        let router = Router::new()
+            .route("/v1/invoices", post(create_invoice))
+            .route(
+                "/v1/invoices/handle/:handle",
+                post(create_invoice_for_handle),
+            )
+            .route("/v1/invoices/:invoice_id", get(get_invoice))
+            .route("/v1/invoices/:invoice_id/quote", post(invoice_quote))
+            .route("/v1/payment-quotes/lightning", post(payment_quote))
+            .route(
+                "/v1/payment-quotes/:payment_quote_id/execute",
+                patch(execute_payment_quote),
+            )
+            .route("/v1/payments/:payment_id", get(get_payment))
+            .route(
+                "/v1/subscriptions",
+                post(create_subscription).get(get_subscriptions),
+            )
+            .route(
+                "/v1/subscriptions/:subscription_id",
+                delete(delete_subscription),
+            )
+            .layer(middleware::from_fn_with_state(shared.clone(), intercept))
+            .with_state(shared.clone());
+
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = axum::Server::from_tcp(listener)?
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(async {
+                shutdown_rx.await.ok();
+            });
+
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                log::error!("Mock Strike server failed: {}", err);
+            }
+        });
+
+        Ok(Self {
+            addr,
+            shared,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Url of the mock server
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Client for the mock server using [`MOCK_API_KEY`]
+    ///
+    /// Retries back off for milliseconds rather than seconds
+    pub fn client(&self) -> Result<Strike> {
+        Strike::builder(MOCK_API_KEY)
+            .base_url(&self.url())
+            .retry_policy(RetryPolicy::exponential(
+                3,
+                Duration::from_millis(5),
+                Duration::from_millis(20),
+            ))
+            .build()
+    }
+
+    /// Set the state of an invoice and deliver `invoice.updated`
+    pub async fn set_invoice_state(&self, invoice_id: &str, state: InvoiceState) -> Result<()> {
+        {
+            let mut mock = self.shared.lock();
+            let invoice = mock
+                .invoices
+                .get_mut(invoice_id)
+                .ok_or_else(|| anyhow!("Unknown invoice {invoice_id}"))?;
+            invoice["state"] = serde_json::to_value(state)?;
+        }
+
+        deliver(&self.shared, "invoice.updated", invoice_id).await;
+
+        Ok(())
+    }
+
+    /// Mark an invoice paid and deliver `invoice.updated`
+    pub async fn pay_invoice(&self, invoice_id: &str) -> Result<()> {
+        self.set_invoice_state(invoice_id, InvoiceState::Paid).await
+    }
+
+    /// Invoice by id
+    pub fn invoice(&self, invoice_id: &str) -> Option<InvoiceResponse> {
+        let mock = self.shared.lock();
+        let invoice = mock.invoices.get(invoice_id)?;
+
+        serde_json::from_value(invoice.clone()).ok()
+    }
+
+    /// State of payments made by executing a quote, defaults to
+    /// [`InvoiceState::Completed`]
+    ///
+    /// Only completed payments pay the mock invoice of the quote
+    pub fn set_payment_outcome(&self, state: InvoiceState) {
+        self.shared.lock().payment_state = state;
+    }
+
+    /// Set the state of a payment
+    ///
+    /// Completing a payment pays the mock invoice of its quote
+    pub async fn set_payment_state(&self, payment_id: &str, state: InvoiceState) -> Result<()> {
+        let paid_invoice = {
+            let mut mock = self.shared.lock();
+            let payment = mock
+                .payments
+                .get_mut(payment_id)
+                .ok_or_else(|| anyhow!("Unknown payment {payment_id}"))?;
+            payment["state"] = serde_json::to_value(&state)?;
+            if state == InvoiceState::Completed {
+                payment["completed"] = json!(now());
+            }
+
+            match state {
+                InvoiceState::Completed => mock.paid_invoice(payment_id),
+                _ => None,
+            }
+        };
+
+        if let Some(invoice_id) = paid_invoice {
+            self.pay_invoice(&invoice_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Rate of one BTC in every fiat currency, defaults to `50000`
+    pub fn set_btc_rate(&self, rate: f64) {
+        self.shared.lock().btc_rate = rate;
+    }
+
+    /// Respond to the next `count` requests with `status`
+    pub fn fail_next(&self, count: usize, status: u16) -> Result<()> {
+        let status = StatusCode::from_u16(status)?;
+        self.shared
+            .lock()
+            .failures
+            .extend(std::iter::repeat(status).take(count));
+
+        Ok(())
+    }
+
+    /// Events created so far, oldest first
+    pub fn events(&self) -> Vec<WebhookEvent> {
+        self.shared.lock().events.clone()
+    }
+}
+
+impl Drop for MockStrike {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl MockState {
+    // Mock invoice paid by `payment_id`, if the invoice is not yet paid
+    fn paid_invoice(&self, payment_id: &str) -> Option<String> {
+        let quote_id = self
+            .executed_quotes
+            .iter()
+            .find(|(_, payment)| payment.as_str() == payment_id)
+            .map(|(quote_id, _)| quote_id)?;
+        let invoice_id = self.quote_invoices.get(quote_id)?;
+
+        match self.invoices.get(invoice_id)?["state"].as_str() {
+            Some("UNPAID") => Some(invoice_id.clone()),
+            _ => None,
+        }
+    }
+}
+
+fn new_id() -> String {
+    IdempotencyKey::generate().to_string()
+}
+
+fn now() -> String {
+    timestamp::format(SystemTime::now())
+}
+
+fn error(status: StatusCode, code: &str, message: &str) -> Response {
+    let body = json!({
+        "data": {
+            "status": status.as_u16(),
+            "code": code,
+            "message": message,
+        }
+    });
+
+    (status, Json(body)).into_response()
+}
+
+fn not_found(entity: &str) -> Response {
+    error(
+        StatusCode::NOT_FOUND,
+        "NOT_FOUND",
+        &format!("{entity} not found"),
+    )
+}
+
+fn currency(value: &Value) -> Option<Currency> {
+    serde_json::from_value(value.clone()).ok()
+}
+
+// Amount from a request, which may send it as a number or string
+fn amount(value: &Value) -> Option<(Currency, f64)> {
+    let currency = currency(value.get("currency")?)?;
+    let amount = match value.get("amount")? {
+        Value::Number(amount) => amount.as_f64()?,
+        Value::String(amount) => amount.parse().ok()?,
+        _ => return None,
+    };
+
+    Some((currency, amount))
+}
+
+fn amount_json(currency: &Currency, amount: f64) -> Value {
+    json!({
+        "currency": currency,
+        "amount": format!("{:.*}", currency.decimals() as usize, amount),
+    })
+}
+
+// Convert through BTC, every fiat currency has the same BTC rate
+fn convert(amount: f64, from: &Currency, to: &Currency, btc_rate: f64) -> f64 {
+    let btc = match from {
+        Currency::BTC => amount,
+        _ => amount / btc_rate,
+    };
+
+    match to {
+        Currency::BTC => btc,
+        _ => btc * btc_rate,
+    }
+}
+
+// Rate between `from` and `to` in the direction `Amount::convert` reads it,
+// the price of one BTC for pairs with BTC and one `from` in `to` otherwise
+fn conversion_rate(from: &Currency, to: &Currency, btc_rate: f64) -> Value {
+    let (amount, source, target) = match (from, to) {
+        _ if from == to => (1.0, from, to),
+        (Currency::BTC, fiat) | (fiat, Currency::BTC) => (btc_rate, &Currency::BTC, fiat),
+        // Every fiat currency has the same BTC rate
+        _ => (1.0, from, to),
+    };
+
+    json!({
+        "amount": format!("{amount:.2}"),
+        "sourceCurrency": source,
+        "targetCurrency": target,
+    })
+}
+
+// Checks auth, injects failures and replays idempotent requests
+async fn intercept<B>(
+    State(shared): State<Shared>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        == Some(format!("Bearer {MOCK_API_KEY}").as_str());
+    if !authorized {
+        return error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "Invalid api key");
+    }
+
+    let failure = {
+        let mut mock = shared.lock();
+        match mock.failures.is_empty() {
+            true => None,
+            false => Some(mock.failures.remove(0)),
+        }
+    };
+    if let Some(status) = failure {
+        return error(status, "MOCK_FAILURE", "Injected failure");
+    }
+
+    let idempotency_key = match *request.method() {
+        Method::POST | Method::PATCH => request
+            .headers()
+            .get(IDEMPOTENCY_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|key| format!("{} {} {}", request.method(), request.uri().path(), key)),
+        _ => None,
+    };
+
+    let idempotency_key = match idempotency_key {
+        Some(key) => key,
+        None => return next.run(request).await,
+    };
+
+    let replay = shared.lock().idempotent.get(&idempotency_key).cloned();
+    if let Some((status, body)) = replay {
+        return (status, [(CONTENT_TYPE, "application/json")], body).into_response();
+    }
+
+    let (parts, body) = next.run(request).await.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(err) => {
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "MOCK_ERROR",
+                &err.to_string(),
+            )
+        }
+    };
+
+    if parts.status.is_success() {
+        shared
+            .lock()
+            .idempotent
+            .insert(idempotency_key, (parts.status, body.clone()));
+    }
+
+    Response::from_parts(parts, body::boxed(Full::from(body)))
+}
+
+async fn create_invoice(State(shared): State<Shared>, Json(request): Json<Value>) -> Response {
+    insert_invoice(&shared, &request, MOCK_ACCOUNT_ID)
+}
+
+async fn create_invoice_for_handle(
+    State(shared): State<Shared>,
+    Path(handle): Path<String>,
+    Json(request): Json<Value>,
+) -> Response {
+    insert_invoice(&shared, &request, &format!("account-{handle}"))
+}
+
+fn insert_invoice(shared: &Shared, request: &Value, receiver_id: &str) -> Response {
+    let (currency, amount) = match amount(&request["amount"]) {
+        Some(amount) => amount,
+        None => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "INVALID_DATA",
+                "Invalid invoice amount",
+            )
+        }
+    };
+
+    let invoice_id = new_id();
+    let invoice = json!({
+        "invoiceId": invoice_id,
+        "amount": amount_json(&currency, amount),
+        "state": "UNPAID",
+        "created": now(),
+        "correlationId": request["correlationId"],
+        "description": request["description"],
+        "issuerId": MOCK_ACCOUNT_ID,
+        "receiverId": receiver_id,
+    });
+
+    shared.lock().invoices.insert(invoice_id, invoice.clone());
+
+    (StatusCode::CREATED, Json(invoice)).into_response()
+}
+
+async fn get_invoice(State(shared): State<Shared>, Path(invoice_id): Path<String>) -> Response {
+    match shared.lock().invoices.get(&invoice_id) {
+        Some(invoice) => Json(invoice.clone()).into_response(),
+        None => not_found("Invoice"),
+    }
+}
+
+async fn invoice_quote(State(shared): State<Shared>, Path(invoice_id): Path<String>) -> Response {
+    let mut mock = shared.lock();
+
+    let invoice = match mock.invoices.get(&invoice_id) {
+        Some(invoice) => invoice.clone(),
+        None => return not_found("Invoice"),
+    };
+    if invoice["state"] != "UNPAID" {
+        return error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "INVALID_STATE_FOR_INVOICE_QUOTE",
+            "Invoice is not unpaid",
+        );
+    }
+
+    let (currency, amount) = match amount(&invoice["amount"]) {
+        Some(amount) => amount,
+        None => {
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "MOCK_ERROR",
+                "Bad amount",
+            )
+        }
+    };
+    let btc = convert(amount, &currency, &Currency::BTC, mock.btc_rate);
+
+    let ln_invoice = format!("lnbcrt{}", invoice_id.replace('-', ""));
+    mock.ln_invoices.insert(ln_invoice.clone(), invoice_id);
+
+    let quote = json!({
+        "quoteId": new_id(),
+        "description": invoice["description"],
+        "lnInvoice": ln_invoice,
+        "onchainAddress": null,
+        "expiration": timestamp::format(SystemTime::now() + Duration::from_secs(30)),
+        "expirationInSec": 30,
+        "sourceAmount": amount_json(&Currency::BTC, btc),
+        "targetAmount": invoice["amount"],
+        "conversionRate": conversion_rate(&Currency::BTC, &currency, mock.btc_rate),
+    });
+
+    (StatusCode::CREATED, Json(quote)).into_response()
+}
+
+async fn payment_quote(State(shared): State<Shared>, Json(request): Json<Value>) -> Response {
+    let mut mock = shared.lock();
+
+    let source_currency = match currency(&request["sourceCurrency"]) {
+        Some(currency) => currency,
+        None => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "INVALID_DATA",
+                "Invalid source currency",
+            )
+        }
+    };
+
+    // Only quotes of mock invoices can be paid
+    let invoice_id = match request["lnInvoice"]
+        .as_str()
+        .and_then(|ln_invoice| mock.ln_invoices.get(ln_invoice))
+    {
+        Some(invoice_id) => invoice_id.clone(),
+        None => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "INVALID_LN_INVOICE",
+                "Unknown lightning invoice",
+            )
+        }
+    };
+
+    let invoice = mock.invoices[&invoice_id].clone();
+    let (currency, amount) = match amount(&invoice["amount"]) {
+        Some(amount) => amount,
+        None => {
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "MOCK_ERROR",
+                "Bad amount",
+            )
+        }
+    };
+    let source_amount = convert(amount, &currency, &source_currency, mock.btc_rate);
+
+    let conversion_rate = match source_currency == currency {
+        true => Value::Null,
+        false => conversion_rate(&source_currency, &currency, mock.btc_rate),
+    };
+
+    let payment_quote_id = new_id();
+    let quote = json!({
+        "paymentQuoteId": payment_quote_id,
+        "description": invoice["description"],
+        "validUntil": timestamp::format(SystemTime::now() + Duration::from_secs(60)),
+        "conversionRate": conversion_rate,
+        "amount": amount_json(&source_currency, source_amount),
+        "lightningNetworkFee": amount_json(&source_currency, 0.0),
+        "totalAmount": amount_json(&source_currency, source_amount),
+    });
+
+    mock.payment_quotes
+        .insert(payment_quote_id.clone(), quote.clone());
+    mock.quote_invoices.insert(payment_quote_id, invoice_id);
+
+    (StatusCode::CREATED, Json(quote)).into_response()
+}
+
+async fn execute_payment_quote(
+    State(shared): State<Shared>,
+    Path(payment_quote_id): Path<String>,
+) -> Response {
+    let (payment, paid_invoice) = {
+        let mut mock = shared.lock();
+
+        let quote = match mock.payment_quotes.get(&payment_quote_id) {
+            Some(quote) => quote.clone(),
+            None => return not_found("Payment quote"),
+        };
+        if mock.executed_quotes.contains_key(&payment_quote_id) {
+            return error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "PAYMENT_QUOTE_ALREADY_EXECUTED",
+                "Payment quote was already executed",
+            );
+        }
+
+        let state = mock.payment_state.clone();
+        let payment_id = new_id();
+        let payment = json!({
+            "paymentId": payment_id,
+            "state": state,
+            "completed": match state {
+                InvoiceState::Completed => json!(now()),
+                _ => Value::Null,
+            },
+            "conversionRate": quote["conversionRate"],
+            "amount": quote["amount"],
+            "lightningNetworkFee": quote["lightningNetworkFee"],
+            "totalAmount": quote["totalAmount"],
+        });
+
+        mock.payments.insert(payment_id.clone(), payment.clone());
+        mock.executed_quotes
+            .insert(payment_quote_id, payment_id.clone());
+
+        let paid_invoice = match state {
+            InvoiceState::Completed => mock.paid_invoice(&payment_id),
+            _ => None,
+        };
+        if let Some(invoice) = paid_invoice
+            .as_ref()
+            .and_then(|invoice_id| mock.invoices.get_mut(invoice_id))
+        {
+            invoice["state"] = json!("PAID");
+        }
+
+        (payment, paid_invoice)
+    };
+
+    if let Some(invoice_id) = paid_invoice {
+        deliver(&shared, "invoice.updated", &invoice_id).await;
+    }
+
+    Json(payment).into_response()
+}
+
+async fn get_payment(State(shared): State<Shared>, Path(payment_id): Path<String>) -> Response {
+    match shared.lock().payments.get(&payment_id) {
+        Some(payment) => Json(payment.clone()).into_response(),
+        None => not_found("Payment"),
+    }
+}
+
+async fn create_subscription(State(shared): State<Shared>, Json(request): Json<Value>) -> Response {
+    let webhook_url = request["webhookUrl"].as_str();
+    let secret = request["secret"].as_str();
+
+    let (webhook_url, secret) = match (webhook_url, secret) {
+        (Some(webhook_url), Some(secret)) => (webhook_url.to_string(), secret.to_string()),
+        _ => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "INVALID_DATA",
+                "Webhook url and secret are required",
+            )
+        }
+    };
+
+    let subscription = Subscription {
+        id: new_id(),
+        webhook_url,
+        webhook_version: request["webhookVersion"]
+            .as_str()
+            .unwrap_or("v1")
+            .to_string(),
+        secret,
+        enabled: request["enabled"].as_bool().unwrap_or(true),
+        event_types: serde_json::from_value(request["eventTypes"].clone()).unwrap_or_default(),
+        created: now(),
+    };
+
+    let body = subscription.to_json();
+    shared.lock().subscriptions.push(subscription);
+
+    (StatusCode::CREATED, Json(body)).into_response()
+}
+
+async fn get_subscriptions(State(shared): State<Shared>) -> Response {
+    let subscriptions: Vec<Value> = shared
+        .lock()
+        .subscriptions
+        .iter()
+        .map(Subscription::to_json)
+        .collect();
+
+    Json(subscriptions).into_response()
+}
+
+async fn delete_subscription(
+    State(shared): State<Shared>,
+    Path(subscription_id): Path<String>,
+) -> Response {
+    let mut mock = shared.lock();
+
+    match mock
+        .subscriptions
+        .iter()
+        .position(|subscription| subscription.id == subscription_id)
+    {
+        Some(index) => {
+            mock.subscriptions.remove(index);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => not_found("Subscription"),
+    }
+}
+
+// Record event and post it, signed, to each enabled subscription for its type
+async fn deliver(shared: &Shared, event_type: &str, entity_id: &str) {
+    let (event, subscriptions) = {
+        let mut mock = shared.lock();
+
+        let event = WebhookEvent {
+            id: new_id(),
+            event_type: event_type.to_string(),
+            webhook_version: "v1".to_string(),
+            data: WebHookData {
+                entity_id: entity_id.to_string(),
+                changes: vec!["state".to_string()],
+            },
+            created: now(),
+            delivery_success: None,
+        };
+        mock.events.push(event.clone());
+
+        let subscriptions: Vec<Subscription> = mock
+            .subscriptions
+            .iter()
+            .filter(|subscription| {
+                subscription.enabled && subscription.event_types.iter().any(|t| t == event_type)
+            })
+            .cloned()
+            .collect();
+
+        (event, subscriptions)
+    };
+
+    if subscriptions.is_empty() {
+        return;
+    }
+
+    let body = match serde_json::to_vec(&event) {
+        Ok(body) => body,
+        Err(err) => {
+            log::error!("Could not serialize mock event: {}", err);
+            return;
+        }
+    };
+
+    let mut delivered = true;
+    for subscription in subscriptions {
+        let res = shared
+            .client
+            .post(&subscription.webhook_url)
+            .header(
+                SIGNATURE_HEADER,
+                sign_webhook(&body, subscription.secret.as_bytes()),
+            )
+            .header(CONTENT_TYPE.as_str(), "application/json")
+            .body(body.clone())
+            .send()
+            .await;
+
+        match res {
+            Ok(res) if res.status().is_success() => (),
+            Ok(res) => {
+                log::warn!(
+                    "Mock webhook to {} returned {}",
+                    subscription.webhook_url,
+                    res.status()
+                );
+                delivered = false;
+            }
+            Err(err) => {
+                log::warn!(
+                    "Mock webhook to {} failed: {}",
+                    subscription.webhook_url,
+                    err
+                );
+                delivered = false;
+            }
+        }
+    }
+
+    let mut mock = shared.lock();
+    if let Some(recorded) = mock.events.iter_mut().find(|e| e.id == event.id) {
+        recorded.delivery_success = Some(delivered);
+    }
+}
@@ -4,7 +4,9 @@ use anyhow::{bail, Result};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
-use crate::{Amount, ConversionRate, Currency, Error, InvoiceState, Strike};
+use crate::{
+    Amount, ConversionRate, Currency, Error, Idempotent, InvoiceState, QuotedConversion, Strike,
+};
 
 /// Pay Invoice Request
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -16,6 +18,31 @@ pub struct PayInvoiceQuoteRequest {
     pub source_currency: Currency,
 }
 
+/// Pay Strike handle quote request
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayHandleQuoteRequest {
+    /// Strike handle to pay, on production
+    pub handle: String,
+    /// [`Amount`] the handle receives
+    pub amount: Amount,
+    /// Currency to pay from
+    pub source_currency: Currency,
+    /// Description
+    pub description: Option<String>,
+}
+
+/// LNURL payment quote request
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LnurlQuoteRequest {
+    ln_address_or_url: String,
+    source_currency: Currency,
+    amount: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
 /// Pay Invoice Response
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -56,20 +83,38 @@ pub struct InvoicePaymentResponse {
     pub total_amount: Amount,
 }
 
+impl QuotedConversion for PayInvoiceQuoteResponse {
+    fn conversion_rate(&self) -> Option<&ConversionRate> {
+        self.conversion_rate.as_ref()
+    }
+}
+
+impl QuotedConversion for InvoicePaymentResponse {
+    fn conversion_rate(&self) -> Option<&ConversionRate> {
+        self.conversion_rate.as_ref()
+    }
+}
+
 impl Strike {
     /// Create Payment Quote
     pub async fn payment_quote(
         &self,
         quote_request: PayInvoiceQuoteRequest,
-    ) -> Result<PayInvoiceQuoteResponse> {
-        let url = self.base_url.join("/v1/payment-quotes/lightning")?;
+    ) -> Result<Idempotent<PayInvoiceQuoteResponse>> {
+        let url = self.url(&["v1", "payment-quotes", "lightning"])?;
+
+        let idempotency_key = self.idempotency_key();
 
         let res = self
-            .make_post(url, Some(serde_json::to_value(quote_request)?))
+            .make_post(
+                url,
+                Some(serde_json::to_value(quote_request)?),
+                &idempotency_key,
+            )
             .await?;
 
         match serde_json::from_value(res.clone()) {
-            Ok(res) => Ok(res),
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
             Err(_) => {
                 log::error!("Api error response on payment quote");
                 log::error!("{}", res);
@@ -78,16 +123,100 @@ impl Strike {
         }
     }
 
+    /// Create Payment Quote to a Strike handle
+    ///
+    /// Execute the quote with [`Strike::pay_quote`]
+    ///
+    /// The handle is paid through its lightning address on the domain of the
+    /// client's [`Environment`]. Only production has handle addresses, other
+    /// environments fail with [`Error::UnsupportedEnvironment`] before
+    /// anything is sent. Refused by
+    /// [`StrikeBuilder::deny_production_payments`].
+    ///
+    /// [`Environment`]: crate::Environment
+    /// [`StrikeBuilder::deny_production_payments`]: crate::StrikeBuilder::deny_production_payments
+    pub async fn payment_quote_to_handle(
+        &self,
+        quote_request: PayHandleQuoteRequest,
+    ) -> Result<Idempotent<PayInvoiceQuoteResponse>> {
+        let url = self.url(&["v1", "payment-quotes", "lightning", "lnurl"])?;
+
+        // Handle addresses resolve on production, whatever the api url
+        if self.deny_production_payments {
+            bail!(Error::ProductionPaymentDenied);
+        }
+
+        let domain = self
+            .environment
+            .handle_domain()
+            .ok_or_else(|| Error::UnsupportedEnvironment(self.environment.clone()))?;
+        let handle = quote_request.handle.trim_start_matches('$');
+
+        let lnurl_request = LnurlQuoteRequest {
+            ln_address_or_url: format!("{handle}@{domain}"),
+            source_currency: quote_request.source_currency,
+            amount: quote_request.amount,
+            description: quote_request.description,
+        };
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(
+                url,
+                Some(serde_json::to_value(lnurl_request)?),
+                &idempotency_key,
+            )
+            .await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on handle payment quote");
+                log::error!("{}", res);
+                bail!("Could not get handle payment quote")
+            }
+        }
+    }
+
+    /// Quote and execute payment to a Strike handle
+    ///
+    /// Production only and refused by the production payment guard, see
+    /// [`Strike::payment_quote_to_handle`].
+    ///
+    /// A key from [`Strike::with_idempotency_key`] is sent with the payment,
+    /// the quote gets a new key
+    pub async fn pay_handle(
+        &self,
+        quote_request: PayHandleQuoteRequest,
+    ) -> Result<Idempotent<InvoicePaymentResponse>> {
+        // The supplied key is for the payment, not the quote
+        let quoter = Self {
+            idempotency_key: Default::default(),
+            ..self.clone()
+        };
+        let quote = quoter.payment_quote_to_handle(quote_request).await?;
+
+        self.pay_quote(&quote.payment_quote_id).await
+    }
+
     /// Execute quote to pay invoice
-    pub async fn pay_quote(&self, payment_quote_id: &str) -> Result<InvoicePaymentResponse> {
-        let url = self
-            .base_url
-            .join(&format!("/v1/payment-quotes/{payment_quote_id}/execute"))?;
+    ///
+    /// Not retried, see [`retry`](crate::retry). If the call fails without a
+    /// response, repeat it with the returned key via
+    /// [`Strike::with_idempotency_key`] so the quote is paid once
+    pub async fn pay_quote(
+        &self,
+        payment_quote_id: &str,
+    ) -> Result<Idempotent<InvoicePaymentResponse>> {
+        let url = self.url(&["v1", "payment-quotes", payment_quote_id, "execute"])?;
 
-        let res = self.make_patch(url).await?;
+        let idempotency_key = self.idempotency_key();
+
+        let res = self.make_patch(url, &idempotency_key).await?;
 
         match serde_json::from_value(res.clone()) {
-            Ok(res) => Ok(res),
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
             Err(_) => {
                 log::error!("Api error response on payment quote execution");
                 log::error!("{}", res);
@@ -101,22 +230,14 @@ impl Strike {
         &self,
         payment_id: &str,
     ) -> Result<InvoicePaymentResponse, Error> {
-        let url = self
-            .base_url
-            .join(&format!("/v1/payments/{payment_id}"))
-            .map_err(|_| Error::InvalidUrl)?;
+        let url = self.url(&["v1", "payments", payment_id])?;
 
-        let res = match self.make_get(url).await {
-            Ok(res) => res,
-            Err(err) => {
-                if let Error::ReqwestError(err) = &err {
-                    if err.status().unwrap_or_default() == StatusCode::NOT_FOUND {
-                        return Err(Error::NotFound);
-                    }
-                }
-                return Err(err);
-            }
-        };
+        let res = self.get_response(url).await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NotFound);
+        }
+
+        let res: serde_json::Value = serde_json::from_slice(res.body()).unwrap_or_default();
 
         match serde_json::from_value(res.clone()) {
             Ok(res) => Ok(res),
@@ -129,3 +250,129 @@ impl Strike {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::retry::IDEMPOTENCY_KEY_HEADER;
+    use crate::transport::fake::FakeTransport;
+    use crate::Environment;
+
+    fn amount() -> serde_json::Value {
+        json!({ "currency": "BTC", "amount": "0.0001" })
+    }
+
+    #[tokio::test]
+    async fn test_pay_handle() {
+        let fake = FakeTransport::default();
+        fake.respond_json(
+            201,
+            json!({
+                "paymentQuoteId": "quote-1",
+                "description": null,
+                "validUntil": "2024-01-01T00:00:10+00:00",
+                "conversionRate": null,
+                "amount": amount(),
+                "lightningNetworkFee": amount(),
+                "totalAmount": amount()
+            }),
+        )
+        .respond_json(
+            200,
+            json!({
+                "paymentId": "payment-1",
+                "state": "COMPLETED",
+                "completed": null,
+                "conversionRate": null,
+                "amount": amount(),
+                "lightningNetworkFee": amount(),
+                "totalAmount": amount()
+            }),
+        );
+
+        let payment = fake
+            .client()
+            .with_idempotency_key("payment-key")
+            .pay_handle(PayHandleQuoteRequest {
+                handle: "$alice".to_string(),
+                amount: Amount {
+                    currency: Currency::BTC,
+                    amount: 0.0001,
+                },
+                source_currency: Currency::BTC,
+                description: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(payment.payment_id, "payment-1");
+        assert_eq!(payment.idempotency_key.as_str(), "payment-key");
+        assert_eq!(
+            fake.calls(),
+            [
+                "POST /v1/payment-quotes/lightning/lnurl",
+                "PATCH /v1/payment-quotes/quote-1/execute"
+            ]
+        );
+        assert_eq!(fake.bodies()[0]["lnAddressOrUrl"], "alice@strike.me");
+
+        // The payment key is not spent on the quote
+        let requests = fake.requests();
+        assert_ne!(requests[0].headers()[IDEMPOTENCY_KEY_HEADER], "payment-key");
+        assert_eq!(requests[1].headers()[IDEMPOTENCY_KEY_HEADER], "payment-key");
+    }
+
+    #[tokio::test]
+    async fn test_handle_not_paid_outside_production() {
+        for environment in [
+            Environment::Custom("http://127.0.0.1:3000".parse().unwrap()),
+            Environment::Custom("https://api.dev.strike.me".parse().unwrap()),
+        ] {
+            let fake = FakeTransport::default();
+            let strike = Strike::builder("api-key")
+                .environment(environment.clone())
+                .transport(fake.clone())
+                .build()
+                .unwrap();
+
+            let err = strike
+                .pay_handle(PayHandleQuoteRequest {
+                    handle: "alice".to_string(),
+                    amount: Amount {
+                        currency: Currency::BTC,
+                        amount: 0.0001,
+                    },
+                    source_currency: Currency::BTC,
+                    description: None,
+                })
+                .await
+                .unwrap_err();
+
+            assert!(matches!(
+                err.downcast_ref::<Error>(),
+                Some(Error::UnsupportedEnvironment(env)) if env == &environment
+            ));
+            assert!(fake.requests().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outgoing_payment_not_found() {
+        let fake = FakeTransport::default();
+        fake.respond_json(
+            404,
+            json!({ "data": { "status": 404, "code": "NOT_FOUND" } }),
+        );
+
+        let err = fake
+            .client()
+            .get_outgoing_payment("payment-1")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::NotFound));
+        assert_eq!(fake.calls(), ["GET /v1/payments/payment-1"]);
+    }
+}
@@ -0,0 +1,516 @@
+//! Bank payment methods
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{append_list_query, Address, Currency, Error, Idempotent, Page, Strike};
+
+/// Bank transfer rail
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransferType {
+    /// US ACH
+    Ach,
+    /// US domestic wire
+    UsDomesticWire,
+    /// SEPA
+    Sepa,
+    /// UK Faster Payments
+    Fps,
+}
+
+/// Bank account type
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AccountType {
+    /// Checking account
+    Checking,
+    /// Savings account
+    Savings,
+}
+
+/// ACH bank details
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AchDetails {
+    /// Account holder name
+    pub account_holder_name: String,
+    /// Account number
+    pub account_number: String,
+    /// ABA routing number
+    pub routing_number: String,
+    /// Account type
+    pub account_type: AccountType,
+}
+
+/// US domestic wire bank details
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WireDetails {
+    /// Account holder name
+    pub account_holder_name: String,
+    /// Account number
+    pub account_number: String,
+    /// ABA routing number
+    pub routing_number: String,
+    /// Bank name
+    pub bank_name: String,
+    /// Bank address
+    pub bank_address: Address,
+}
+
+/// SEPA bank details
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SepaDetails {
+    /// Account holder name
+    pub account_holder_name: String,
+    /// IBAN
+    pub iban: String,
+    /// BIC
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bic: Option<String>,
+}
+
+/// UK Faster Payments bank details
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FpsDetails {
+    /// Account holder name
+    pub account_holder_name: String,
+    /// Account number
+    pub account_number: String,
+    /// Sort code
+    pub sort_code: String,
+}
+
+/// Bank details for a transfer rail
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "transferType", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BankDetails {
+    /// US ACH
+    Ach(AchDetails),
+    /// US domestic wire
+    UsDomesticWire(WireDetails),
+    /// SEPA
+    Sepa(SepaDetails),
+    /// UK Faster Payments
+    Fps(FpsDetails),
+}
+
+impl BankDetails {
+    /// Transfer rail
+    pub fn transfer_type(&self) -> TransferType {
+        match self {
+            Self::Ach(_) => TransferType::Ach,
+            Self::UsDomesticWire(_) => TransferType::UsDomesticWire,
+            Self::Sepa(_) => TransferType::Sepa,
+            Self::Fps(_) => TransferType::Fps,
+        }
+    }
+
+    /// Bank details without separators
+    ///
+    /// Whitespace is dropped from IBANs, BICs and sort codes and hyphens from
+    /// sort codes. IBANs and BICs are uppercased.
+    pub fn normalized(mut self) -> Self {
+        match &mut self {
+            Self::Sepa(details) => {
+                details.iban = strip(&details.iban, &[]).to_ascii_uppercase();
+                details.bic = details
+                    .bic
+                    .as_deref()
+                    .map(|bic| strip(bic, &[]).to_ascii_uppercase());
+            }
+            Self::Fps(details) => details.sort_code = strip(&details.sort_code, &['-']),
+            Self::Ach(_) | Self::UsDomesticWire(_) => (),
+        }
+
+        self
+    }
+
+    /// Check the fields the rail requires are present and well formed
+    ///
+    /// Separators are accepted, see [`BankDetails::normalized`]
+    pub fn validate(&self) -> Result<(), Error> {
+        match &self.clone().normalized() {
+            Self::Ach(details) => {
+                require_name(&details.account_holder_name)?;
+                validate_routing_number(&details.routing_number)?;
+                validate_digits("account number", &details.account_number, 4, 17)
+            }
+            Self::UsDomesticWire(details) => {
+                require_name(&details.account_holder_name)?;
+                validate_routing_number(&details.routing_number)?;
+                validate_digits("account number", &details.account_number, 4, 17)?;
+                if details.bank_name.trim().is_empty() {
+                    return Err(invalid("bank name is required"));
+                }
+                if details.bank_address.country.trim().is_empty() {
+                    return Err(invalid("bank address country is required"));
+                }
+                Ok(())
+            }
+            Self::Sepa(details) => {
+                require_name(&details.account_holder_name)?;
+                validate_iban(&details.iban)?;
+                match &details.bic {
+                    Some(bic) => validate_bic(bic),
+                    None => Ok(()),
+                }
+            }
+            Self::Fps(details) => {
+                require_name(&details.account_holder_name)?;
+                validate_digits("sort code", &details.sort_code, 6, 6)?;
+                validate_digits("account number", &details.account_number, 8, 8)
+            }
+        }
+    }
+}
+
+// `value` without whitespace and `separators`
+fn strip(value: &str, separators: &[char]) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !separators.contains(c))
+        .collect()
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidPaymentMethod(reason.to_string())
+}
+
+fn require_name(name: &str) -> Result<(), Error> {
+    match name.trim().is_empty() {
+        true => Err(invalid("account holder name is required")),
+        false => Ok(()),
+    }
+}
+
+fn validate_digits(field: &str, value: &str, min: usize, max: usize) -> Result<(), Error> {
+    if value.len() < min || value.len() > max || !value.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(Error::InvalidPaymentMethod(match min == max {
+            true => format!("{field} must be {min} digits"),
+            false => format!("{field} must be {min} to {max} digits"),
+        }));
+    }
+
+    Ok(())
+}
+
+// ABA routing numbers carry a 3-7-1 weighted checksum
+fn validate_routing_number(routing_number: &str) -> Result<(), Error> {
+    validate_digits("routing number", routing_number, 9, 9)?;
+
+    let checksum: u32 = routing_number
+        .bytes()
+        .zip([3, 7, 1, 3, 7, 1, 3, 7, 1])
+        .map(|(c, weight)| (c - b'0') as u32 * weight)
+        .sum();
+
+    match checksum % 10 {
+        0 => Ok(()),
+        _ => Err(invalid("routing number checksum is invalid")),
+    }
+}
+
+// ISO 13616 mod 97 check
+fn validate_iban(iban: &str) -> Result<(), Error> {
+    if iban.len() < 15 || iban.len() > 34 || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid("IBAN must be 15 to 34 alphanumeric characters"));
+    }
+
+    let (country, rest) = iban.split_at(4);
+    let remainder = rest
+        .chars()
+        .chain(country.chars())
+        .try_fold(0u32, |acc, c| {
+            let value = c.to_digit(36)?;
+            Some(match value {
+                0..=9 => (acc * 10 + value) % 97,
+                _ => (acc * 100 + value) % 97,
+            })
+        });
+
+    match remainder {
+        Some(1) => Ok(()),
+        _ => Err(invalid("IBAN checksum is invalid")),
+    }
+}
+
+fn validate_bic(bic: &str) -> Result<(), Error> {
+    match (bic.len() == 8 || bic.len() == 11) && bic.chars().all(|c| c.is_ascii_alphanumeric()) {
+        true => Ok(()),
+        false => Err(invalid("BIC must be 8 or 11 alphanumeric characters")),
+    }
+}
+
+/// Payment method state
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PaymentMethodState {
+    /// Awaiting verification
+    Pending,
+    /// Usable for deposits and payouts
+    Active,
+    /// Suspended
+    Suspended,
+    /// Removed
+    Inactive,
+}
+
+/// Bank payment method response
+///
+/// Account numbers are masked by Strike
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankPaymentMethodResponse {
+    /// Payment method id
+    pub id: String,
+    /// Payment method state
+    pub state: PaymentMethodState,
+    /// Transfer rail
+    pub transfer_type: TransferType,
+    /// Account holder name
+    pub account_holder_name: Option<String>,
+    /// Masked account number
+    pub account_number: Option<String>,
+    /// Masked IBAN
+    pub iban: Option<String>,
+    /// Bank name
+    pub bank_name: Option<String>,
+    /// Currency of the account
+    pub currency: Option<Currency>,
+    /// Created timestamp
+    pub created: String,
+}
+
+impl Strike {
+    /// Create bank payment method
+    ///
+    /// `bank_details` are normalized and validated before anything is sent
+    pub async fn create_bank_payment_method(
+        &self,
+        bank_details: BankDetails,
+    ) -> Result<Idempotent<BankPaymentMethodResponse>> {
+        let bank_details = bank_details.normalized();
+        bank_details.validate()?;
+
+        let url = self.url(&["v1", "payment-methods", "bank"])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(
+                url,
+                Some(serde_json::to_value(bank_details)?),
+                &idempotency_key,
+            )
+            .await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on bank payment method creation");
+                log::error!("{}", res);
+                bail!("Could not create bank payment method")
+            }
+        }
+    }
+
+    /// Get bank payment method by id
+    pub async fn get_bank_payment_method(
+        &self,
+        payment_method_id: &str,
+    ) -> Result<BankPaymentMethodResponse> {
+        let url = self.url(&["v1", "payment-methods", "bank", payment_method_id])?;
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on get bank payment method");
+                log::error!("{}", res);
+                bail!("Could not get bank payment method")
+            }
+        }
+    }
+
+    /// List bank payment methods
+    pub async fn get_bank_payment_methods(
+        &self,
+        skip: Option<u32>,
+        top: Option<u32>,
+    ) -> Result<Page<BankPaymentMethodResponse>> {
+        let mut url = self.url(&["v1", "payment-methods", "bank"])?;
+        append_list_query(&mut url, &[], "created desc", skip, top);
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on list bank payment methods");
+                log::error!("{}", res);
+                bail!("Could not list bank payment methods")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn payment_method() -> serde_json::Value {
+        json!({
+            "id": "pm-1",
+            "state": "PENDING",
+            "transferType": "SEPA",
+            "accountHolderName": "Satoshi",
+            "accountNumber": null,
+            "iban": "DE89********3000",
+            "bankName": null,
+            "currency": "EUR",
+            "created": "2024-01-01T00:00:00+00:00"
+        })
+    }
+
+    fn ach(routing_number: &str, account_number: &str) -> BankDetails {
+        BankDetails::Ach(AchDetails {
+            account_holder_name: "Satoshi".to_string(),
+            account_number: account_number.to_string(),
+            routing_number: routing_number.to_string(),
+            account_type: AccountType::Checking,
+        })
+    }
+
+    fn sepa(iban: &str, bic: Option<&str>) -> BankDetails {
+        BankDetails::Sepa(SepaDetails {
+            account_holder_name: "Satoshi".to_string(),
+            iban: iban.to_string(),
+            bic: bic.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_validate_ach() {
+        assert!(ach("011000015", "123456789").validate().is_ok());
+        assert!(ach("011000016", "123456789").validate().is_err());
+        assert!(ach("01100001", "123456789").validate().is_err());
+        assert!(ach("011000015", "12a").validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_sepa() {
+        assert!(sepa("DE89 3704 0044 0532 0130 00", Some("COBADEFFXXX"))
+            .validate()
+            .is_ok());
+        assert!(sepa("DE89370400440532013001", None).validate().is_err());
+        assert!(sepa("DE89370400440532013000", Some("COBA"))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_serialize_tagged() {
+        let value = serde_json::to_value(ach("011000015", "123456789")).unwrap();
+        assert_eq!(value["transferType"], "ACH");
+        assert_eq!(value["routingNumber"], "011000015");
+        assert_eq!(value["accountType"], "CHECKING");
+    }
+
+    #[test]
+    fn test_normalized() {
+        assert_eq!(
+            sepa("de89 3704 0044 0532 0130 00", Some(" cobadeffxxx")).normalized(),
+            sepa("DE89370400440532013000", Some("COBADEFFXXX"))
+        );
+
+        let fps = BankDetails::Fps(FpsDetails {
+            account_holder_name: "Satoshi".to_string(),
+            account_number: "12345678".to_string(),
+            sort_code: "12-34 56".to_string(),
+        });
+        assert!(fps.validate().is_ok());
+        match fps.normalized() {
+            BankDetails::Fps(details) => assert_eq!(details.sort_code, "123456"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_bank_payment_method() {
+        let fake = FakeTransport::default();
+        fake.respond_json(201, payment_method());
+
+        let res = fake
+            .client()
+            .create_bank_payment_method(sepa("DE89 3704 0044 0532 0130 00", None))
+            .await
+            .unwrap();
+
+        assert_eq!(res.id, "pm-1");
+        assert_eq!(res.state, PaymentMethodState::Pending);
+        assert_eq!(fake.calls(), ["POST /v1/payment-methods/bank"]);
+        assert_eq!(
+            fake.bodies(),
+            [json!({
+                "transferType": "SEPA",
+                "accountHolderName": "Satoshi",
+                "iban": "DE89370400440532013000"
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_bank_details_not_sent() {
+        let fake = FakeTransport::default();
+        let strike = fake.client();
+
+        for details in [
+            sepa("DE89370400440532013001", None),
+            ach("011000016", "123456789"),
+        ] {
+            let err = strike
+                .create_bank_payment_method(details)
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<Error>(),
+                Some(Error::InvalidPaymentMethod(_))
+            ));
+        }
+
+        assert!(fake.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_bank_payment_methods() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, payment_method())
+            .respond_json(200, json!({ "items": [payment_method()], "count": 1 }));
+        let strike = fake.client();
+
+        let method = strike.get_bank_payment_method("pm-1").await.unwrap();
+        assert_eq!(method.transfer_type, TransferType::Sepa);
+
+        let page = strike
+            .get_bank_payment_methods(Some(5), Some(10))
+            .await
+            .unwrap();
+        assert_eq!(page.items, [method]);
+
+        assert_eq!(
+            fake.calls(),
+            [
+                "GET /v1/payment-methods/bank/pm-1",
+                "GET /v1/payment-methods/bank?%24orderby=created+desc&%24skip=5&%24top=10"
+            ]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Payment history
+
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+use anyhow::{bail, Result};
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{append_list_query, Amount, ConversionRate, Filter, Page, QuotedConversion, Strike};
+
+/// Outgoing payment type
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PaymentType {
+    /// Lightning payment
+    Lightning,
+    /// Onchain payment
+    Onchain,
+    /// Payment to a Strike handle
+    Handle,
+}
+
+impl PaymentType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lightning => "LIGHTNING",
+            Self::Onchain => "ONCHAIN",
+            Self::Handle => "HANDLE",
+        }
+    }
+}
+
+/// Outgoing payment state
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PaymentState {
+    /// Payment pending
+    Pending,
+    /// Payment completed
+    Completed,
+    /// Payment failed
+    Failed,
+}
+
+impl PaymentState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "PENDING",
+            Self::Completed => "COMPLETED",
+            Self::Failed => "FAILED",
+        }
+    }
+}
+
+/// Outgoing payment
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Payment {
+    /// Payment id
+    pub payment_id: String,
+    /// Payment type
+    #[serde(rename = "type")]
+    pub payment_type: Option<PaymentType>,
+    /// Payment state
+    pub state: PaymentState,
+    /// Created timestamp
+    pub created: Option<String>,
+    /// Completed timestamp
+    pub completed: Option<String>,
+    /// Conversion quote
+    pub conversion_rate: Option<ConversionRate>,
+    /// Amount
+    pub amount: Amount,
+    /// Lightning network fee
+    pub lightning_network_fee: Option<Amount>,
+    /// Onchain network fee
+    pub onchain_network_fee: Option<Amount>,
+    /// Total amount including fee
+    pub total_amount: Amount,
+}
+
+impl QuotedConversion for Payment {
+    fn conversion_rate(&self) -> Option<&ConversionRate> {
+        self.conversion_rate.as_ref()
+    }
+}
+
+/// Payments query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaymentsQuery {
+    /// Only return payments in this state
+    pub state: Option<PaymentState>,
+    /// Only return payments of this type
+    pub payment_type: Option<PaymentType>,
+    /// Only return payments created at or after this time
+    pub created_from: Option<SystemTime>,
+    /// Only return payments created before this time
+    pub created_before: Option<SystemTime>,
+    /// Number of payments to skip
+    pub skip: Option<u32>,
+    /// Max number of payments to return
+    pub top: Option<u32>,
+}
+
+impl PaymentsQuery {
+    fn filter(&self) -> Vec<String> {
+        Filter::default()
+            .eq("state", self.state.as_ref().map(|state| state.as_str()))
+            .eq("type", self.payment_type.as_ref().map(|t| t.as_str()))
+            .created(self.created_from, self.created_before)
+            .into_clauses()
+    }
+}
+
+impl Strike {
+    /// List outgoing payments, oldest first
+    pub async fn list_payments(&self, query: &PaymentsQuery) -> Result<Page<Payment>> {
+        let mut url = self.url(&["v1", "payments"])?;
+        append_list_query(
+            &mut url,
+            &query.filter(),
+            "created asc",
+            query.skip,
+            query.top,
+        );
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on list payments");
+                log::error!("{}", res);
+                bail!("Could not list payments")
+            }
+        }
+    }
+
+    /// Stream every outgoing payment matching `query`, fetching pages as
+    /// needed
+    ///
+    /// `query.top` sets the page size. The stream ends after the first error.
+    pub fn list_all_payments(&self, query: PaymentsQuery) -> impl Stream<Item = Result<Payment>> {
+        let page_size = query.top.unwrap_or(PAYMENTS_PAGE_SIZE).max(1);
+        let skip = query.skip.unwrap_or(0);
+
+        let state = PaymentsStream {
+            strike: self.clone(),
+            query,
+            page_size,
+            skip,
+            buffer: VecDeque::new(),
+            done: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(payment) = state.buffer.pop_front() {
+                    return Some((Ok(payment), state));
+                }
+                if state.done {
+                    return None;
+                }
+
+                let query = PaymentsQuery {
+                    skip: Some(state.skip),
+                    top: Some(state.page_size),
+                    ..state.query.clone()
+                };
+
+                match state.strike.list_payments(&query).await {
+                    Ok(page) => {
+                        let fetched = page.items.len() as u32;
+                        state.skip += fetched;
+                        state.done = fetched < state.page_size;
+                        state.buffer.extend(page.items);
+                    }
+                    Err(err) => {
+                        state.done = true;
+                        return Some((Err(err), state));
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Default page size of [`Strike::list_all_payments`]
+pub const PAYMENTS_PAGE_SIZE: u32 = 100;
+
+struct PaymentsStream {
+    strike: Strike,
+    query: PaymentsQuery,
+    page_size: u32,
+    skip: u32,
+    buffer: VecDeque<Payment>,
+    done: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use futures_util::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn page(ids: &[&str]) -> serde_json::Value {
+        let items: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                json!({
+                    "paymentId": id,
+                    "type": "LIGHTNING",
+                    "state": "COMPLETED",
+                    "created": "2024-01-01T00:00:00+00:00",
+                    "completed": null,
+                    "conversionRate": null,
+                    "amount": { "currency": "BTC", "amount": "0.0001" },
+                    "lightningNetworkFee": null,
+                    "onchainNetworkFee": null,
+                    "totalAmount": { "currency": "BTC", "amount": "0.0001" }
+                })
+            })
+            .collect();
+
+        json!({ "items": items, "count": 5 })
+    }
+
+    #[tokio::test]
+    async fn test_list_all_stops_on_short_page() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, page(&["p1", "p2"]))
+            .respond_json(200, page(&["p3", "p4"]))
+            .respond_json(200, page(&["p5"]));
+
+        let query = PaymentsQuery {
+            payment_type: Some(PaymentType::Lightning),
+            top: Some(2),
+            ..Default::default()
+        };
+        let payments: Vec<_> = fake
+            .client()
+            .list_all_payments(query)
+            .map(|payment| payment.unwrap().payment_id)
+            .collect()
+            .await;
+
+        assert_eq!(payments, ["p1", "p2", "p3", "p4", "p5"]);
+
+        let queries = fake.queries();
+        assert_eq!(queries.len(), 3);
+        for (query, skip) in queries.iter().zip(["0", "2", "4"]) {
+            assert_eq!(query["$skip"], skip);
+            assert_eq!(query["$top"], "2");
+            assert_eq!(query["$filter"], "type eq 'LIGHTNING'");
+            assert_eq!(query["$orderby"], "created asc");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_all_ends_after_error() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, page(&["p1", "p2"]))
+            .respond(400, "{}");
+
+        let query = PaymentsQuery {
+            top: Some(2),
+            ..Default::default()
+        };
+        let payments: Vec<_> = fake.client().list_all_payments(query).collect().await;
+
+        assert_eq!(payments.len(), 3);
+        assert!(payments[2].is_err());
+        assert_eq!(fake.calls().len(), 2);
+    }
+
+    #[test]
+    fn test_filter() {
+        let query = PaymentsQuery {
+            state: Some(PaymentState::Completed),
+            payment_type: Some(PaymentType::Handle),
+            created_from: Some(UNIX_EPOCH + Duration::from_secs(1_704_067_200)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.filter(),
+            [
+                "state eq 'COMPLETED'",
+                "type eq 'HANDLE'",
+                "created ge 2024-01-01T00:00:00+00:00"
+            ]
+        );
+    }
+}
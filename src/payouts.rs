@@ -0,0 +1,436 @@
+//! Payouts
+
+use std::time::SystemTime;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{append_list_query, Amount, FeePolicy, Filter, Idempotent, Page, Strike};
+
+/// Payout request
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoutRequest {
+    /// Bank payment method to pay out to
+    pub payment_method_id: String,
+    /// Payout originator, required for business accounts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub originator_id: Option<String>,
+    /// Amount in the payment method's currency, e.g. `"100.00"`
+    pub amount: String,
+    /// Fee policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_policy: Option<FeePolicy>,
+}
+
+/// Payout state
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PayoutState {
+    /// Payout created, not yet initiated
+    New,
+    /// Payout pending
+    Pending,
+    /// Payout completed
+    Completed,
+    /// Payout failed
+    Failed,
+}
+
+impl PayoutState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::New => "NEW",
+            Self::Pending => "PENDING",
+            Self::Completed => "COMPLETED",
+            Self::Failed => "FAILED",
+        }
+    }
+}
+
+/// Payout response
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoutResponse {
+    /// Payout id
+    pub id: String,
+    /// Payment method id
+    pub payment_method_id: String,
+    /// Payout originator id
+    pub originator_id: Option<String>,
+    /// Payout [`Amount`]
+    pub amount: Amount,
+    /// Fee
+    pub fee: Option<Amount>,
+    /// Amount debited including fee
+    pub total_amount: Option<Amount>,
+    /// Payout state
+    pub state: PayoutState,
+    /// Failure reason
+    pub failure_reason: Option<String>,
+    /// Created timestamp
+    pub created: String,
+    /// Completed timestamp
+    pub completed: Option<String>,
+}
+
+/// Payouts query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayoutsQuery {
+    /// Only return payouts in this state
+    pub state: Option<PayoutState>,
+    /// Only return payouts to this payment method
+    pub payment_method_id: Option<String>,
+    /// Only return payouts created at or after this time
+    pub created_from: Option<SystemTime>,
+    /// Only return payouts created before this time
+    pub created_before: Option<SystemTime>,
+    /// Number of payouts to skip
+    pub skip: Option<u32>,
+    /// Max number of payouts to return
+    pub top: Option<u32>,
+}
+
+impl PayoutsQuery {
+    fn filter(&self) -> Vec<String> {
+        Filter::default()
+            .eq("state", self.state.as_ref().map(|state| state.as_str()))
+            .eq("paymentMethodId", self.payment_method_id.as_deref())
+            .created(self.created_from, self.created_before)
+            .into_clauses()
+    }
+}
+
+/// Postal address
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Address {
+    /// Country as ISO 3166-1 alpha-2 code
+    pub country: String,
+    /// State or province
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// City
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    /// Postal code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_code: Option<String>,
+    /// Address line 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address_line1: Option<String>,
+    /// Address line 2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address_line2: Option<String>,
+}
+
+/// Payout originator type
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OriginatorType {
+    /// Individual
+    Individual,
+    /// Company
+    Company,
+}
+
+/// Payout originator request
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoutOriginatorRequest {
+    /// Originator type
+    #[serde(rename = "type")]
+    pub originator_type: OriginatorType,
+    /// Legal name
+    pub name: String,
+    /// Address
+    pub address: Address,
+}
+
+/// Payout originator response
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoutOriginatorResponse {
+    /// Originator id
+    pub id: String,
+    /// Originator type
+    #[serde(rename = "type")]
+    pub originator_type: OriginatorType,
+    /// Legal name
+    pub name: String,
+    /// Address
+    pub address: Option<Address>,
+    /// Created timestamp
+    pub created: String,
+}
+
+impl Strike {
+    /// Create payout
+    ///
+    /// The payout is not sent until [`Strike::initiate_payout`]
+    pub async fn create_payout(
+        &self,
+        payout_request: PayoutRequest,
+    ) -> Result<Idempotent<PayoutResponse>> {
+        let url = self.url(&["v1", "payouts"])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(
+                url,
+                Some(serde_json::to_value(payout_request)?),
+                &idempotency_key,
+            )
+            .await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on payout creation");
+                log::error!("{}", res);
+                bail!("Could not create payout")
+            }
+        }
+    }
+
+    /// Initiate payout
+    pub async fn initiate_payout(&self, payout_id: &str) -> Result<Idempotent<PayoutResponse>> {
+        let url = self.url(&["v1", "payouts", payout_id, "initiate"])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self.make_patch(url, &idempotency_key).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on payout initiation");
+                log::error!("{}", res);
+                bail!("Could not initiate payout")
+            }
+        }
+    }
+
+    /// Get payout by id
+    pub async fn get_payout(&self, payout_id: &str) -> Result<PayoutResponse> {
+        let url = self.url(&["v1", "payouts", payout_id])?;
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on get payout");
+                log::error!("{}", res);
+                bail!("Could not get payout")
+            }
+        }
+    }
+
+    /// List payouts, newest first
+    pub async fn get_payouts(&self, query: &PayoutsQuery) -> Result<Page<PayoutResponse>> {
+        let mut url = self.url(&["v1", "payouts"])?;
+        append_list_query(
+            &mut url,
+            &query.filter(),
+            "created desc",
+            query.skip,
+            query.top,
+        );
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on list payouts");
+                log::error!("{}", res);
+                bail!("Could not list payouts")
+            }
+        }
+    }
+
+    /// Create payout originator
+    pub async fn create_payout_originator(
+        &self,
+        originator_request: PayoutOriginatorRequest,
+    ) -> Result<Idempotent<PayoutOriginatorResponse>> {
+        let url = self.url(&["v1", "payout-originators"])?;
+
+        let idempotency_key = self.idempotency_key();
+
+        let res = self
+            .make_post(
+                url,
+                Some(serde_json::to_value(originator_request)?),
+                &idempotency_key,
+            )
+            .await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(Idempotent::new(idempotency_key, res)),
+            Err(_) => {
+                log::error!("Api error response on payout originator creation");
+                log::error!("{}", res);
+                bail!("Could not create payout originator")
+            }
+        }
+    }
+
+    /// Get payout originator by id
+    pub async fn get_payout_originator(
+        &self,
+        originator_id: &str,
+    ) -> Result<PayoutOriginatorResponse> {
+        let url = self.url(&["v1", "payout-originators", originator_id])?;
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on get payout originator");
+                log::error!("{}", res);
+                bail!("Could not get payout originator")
+            }
+        }
+    }
+
+    /// List payout originators
+    pub async fn get_payout_originators(
+        &self,
+        skip: Option<u32>,
+        top: Option<u32>,
+    ) -> Result<Page<PayoutOriginatorResponse>> {
+        let mut url = self.url(&["v1", "payout-originators"])?;
+        append_list_query(&mut url, &[], "created desc", skip, top);
+
+        let res = self.make_get(url).await?;
+
+        match serde_json::from_value(res.clone()) {
+            Ok(res) => Ok(res),
+            Err(_) => {
+                log::error!("Api error response on list payout originators");
+                log::error!("{}", res);
+                bail!("Could not list payout originators")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn payout(state: &str) -> serde_json::Value {
+        json!({
+            "id": "payout-1",
+            "paymentMethodId": "pm-1",
+            "originatorId": null,
+            "amount": { "currency": "USD", "amount": "100.00" },
+            "fee": null,
+            "totalAmount": null,
+            "state": state,
+            "failureReason": null,
+            "created": "2024-01-01T00:00:00+00:00",
+            "completed": null
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_and_initiate_payout() {
+        let fake = FakeTransport::default();
+        fake.respond_json(201, payout("NEW"))
+            .respond_json(200, payout("PENDING"));
+        let strike = fake.client();
+
+        let created = strike
+            .create_payout(PayoutRequest {
+                payment_method_id: "pm-1".to_string(),
+                originator_id: None,
+                amount: "100.00".to_string(),
+                fee_policy: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(created.state, PayoutState::New);
+
+        let initiated = strike.initiate_payout(&created.id).await.unwrap();
+        assert_eq!(initiated.state, PayoutState::Pending);
+        assert_ne!(initiated.idempotency_key, created.idempotency_key);
+
+        assert_eq!(
+            fake.calls(),
+            ["POST /v1/payouts", "PATCH /v1/payouts/payout-1/initiate"]
+        );
+        assert_eq!(
+            fake.bodies()[0],
+            json!({ "paymentMethodId": "pm-1", "amount": "100.00" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_payout_originator() {
+        let fake = FakeTransport::default();
+        fake.respond_json(
+            201,
+            json!({
+                "id": "originator-1",
+                "type": "COMPANY",
+                "name": "ACME",
+                "address": { "country": "US" },
+                "created": "2024-01-01T00:00:00+00:00"
+            }),
+        );
+
+        let res = fake
+            .client()
+            .create_payout_originator(PayoutOriginatorRequest {
+                originator_type: OriginatorType::Company,
+                name: "ACME".to_string(),
+                address: Address {
+                    country: "US".to_string(),
+                    city: Some("Chicago".to_string()),
+                    ..Default::default()
+                },
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(res.originator_type, OriginatorType::Company);
+        assert_eq!(fake.calls(), ["POST /v1/payout-originators"]);
+        assert_eq!(
+            fake.bodies()[0],
+            json!({
+                "type": "COMPANY",
+                "name": "ACME",
+                "address": { "country": "US", "city": "Chicago" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_filter_escapes_quotes() {
+        let query = PayoutsQuery {
+            state: Some(PayoutState::Pending),
+            payment_method_id: Some("pm' or state eq 'FAILED".to_string()),
+            created_from: Some(UNIX_EPOCH + Duration::from_millis(1_704_067_200_500)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.filter(),
+            [
+                "state eq 'PENDING'",
+                "paymentMethodId eq 'pm'' or state eq ''FAILED'",
+                "created ge 2024-01-01T00:00:00.5+00:00"
+            ]
+        );
+    }
+}
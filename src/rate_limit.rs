@@ -0,0 +1,233 @@
+//! Rate limits
+//!
+//! Strike reports the quota of the api key in rate limit headers and answers
+//! `429` with a `Retry-After` once it is used up. The most recent quota is
+//! available from [`Strike::rate_limit`], and requests can be throttled on the
+//! client with [`StrikeBuilder::throttle`](crate::StrikeBuilder::throttle).
+//!
+//! A `Retry-After` up to the [`RetryPolicy`](crate::RetryPolicy) max backoff
+//! is waited out, longer waits return the `429` to the caller.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::Strike;
+
+// Reset values above this are unix timestamps rather than seconds from now
+const RESET_TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+/// Rate limit quota reported by Strike
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Requests allowed in the current window
+    pub limit: Option<u64>,
+    /// Requests remaining in the current window
+    pub remaining: Option<u64>,
+    /// When the window resets
+    pub reset: Option<SystemTime>,
+}
+
+impl RateLimit {
+    /// Parse `RateLimit-*` or `X-RateLimit-*` headers
+    ///
+    /// `None` if the response has none of them
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let limit = header_u64(headers, "limit");
+        let remaining = header_u64(headers, "remaining");
+        let reset = header_u64(headers, "reset").map(|reset| match reset {
+            reset if reset > RESET_TIMESTAMP_THRESHOLD => UNIX_EPOCH + Duration::from_secs(reset),
+            reset => SystemTime::now() + Duration::from_secs(reset),
+        });
+
+        match (limit, remaining, reset) {
+            (None, None, None) => None,
+            _ => Some(Self {
+                limit,
+                remaining,
+                reset,
+            }),
+        }
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    [format!("ratelimit-{name}"), format!("x-ratelimit-{name}")]
+        .iter()
+        .find_map(|name| headers.get(name.as_str()))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+// `Retry-After` in seconds, http dates are ignored
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+// Token bucket refilled evenly over `per`
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    capacity: f64,
+    per: Duration,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Throttle {
+    pub(crate) fn new(max_requests: u32, per: Duration) -> Self {
+        let capacity = max_requests.max(1) as f64;
+
+        Self {
+            capacity,
+            per,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    // Wait until a request may be sent
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = match self.try_acquire() {
+                Some(wait) => wait,
+                None => return,
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Take a token, or return how long until one is available
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|err| err.into_inner());
+
+        let now = Instant::now();
+        let rate = self.capacity / self.per.as_secs_f64();
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(self.capacity);
+        bucket.refilled = now;
+
+        match bucket.tokens >= 1.0 {
+            true => {
+                bucket.tokens -= 1.0;
+                None
+            }
+            false => Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate)),
+        }
+    }
+}
+
+impl Strike {
+    /// Quota reported with the most recent response
+    ///
+    /// Shared by clones of the client, `None` until Strike has sent rate
+    /// limit headers
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self
+            .rate_limit
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub(crate) fn record_rate_limit(&self, headers: &HeaderMap) {
+        if let Some(rate_limit) = RateLimit::from_headers(headers) {
+            *self
+                .rate_limit
+                .lock()
+                .unwrap_or_else(|err| err.into_inner()) = Some(rate_limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let rate_limit = RateLimit::from_headers(&headers(&[
+            ("x-ratelimit-limit", "100"),
+            ("x-ratelimit-remaining", "7"),
+            ("x-ratelimit-reset", "1700000000"),
+        ]))
+        .unwrap();
+
+        assert_eq!(rate_limit.limit, Some(100));
+        assert_eq!(rate_limit.remaining, Some(7));
+        assert_eq!(
+            rate_limit.reset,
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+
+        let rate_limit =
+            RateLimit::from_headers(&headers(&[("ratelimit-remaining", "0")])).unwrap();
+        assert_eq!(rate_limit.remaining, Some(0));
+        assert_eq!(rate_limit.limit, None);
+
+        assert!(RateLimit::from_headers(&headers(&[])).is_none());
+    }
+
+    #[test]
+    fn test_retry_after() {
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", "12")])),
+            Some(Duration::from_secs(12))
+        );
+        assert_eq!(
+            retry_after(&headers(&[(
+                "retry-after",
+                "Wed, 21 Oct 2015 07:28:00 GMT"
+            )])),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_is_not_waited() {
+        let fake = FakeTransport::default();
+        fake.respond_with_headers(429, &[("retry-after", "3600")], "{}");
+
+        let started = Instant::now();
+        assert!(fake.client().get_incoming_invoice("id").await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(fake.calls().len(), 1);
+
+        // A wait within the max backoff is retried
+        let fake = FakeTransport::default();
+        fake.respond_with_headers(429, &[("retry-after", "0")], "{}");
+
+        fake.client().get_incoming_invoice("id").await.ok();
+        assert_eq!(fake.calls().len(), 2);
+    }
+
+    #[test]
+    fn test_throttle() {
+        let throttle = Throttle::new(2, Duration::from_secs(10));
+
+        assert!(throttle.try_acquire().is_none());
+        assert!(throttle.try_acquire().is_none());
+
+        let wait = throttle.try_acquire().unwrap();
+        assert!(wait > Duration::from_secs(4) && wait <= Duration::from_secs(5));
+    }
+}
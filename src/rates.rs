@@ -0,0 +1,195 @@
+//! Exchange rates
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::{ConversionRate, Currency, Strike};
+
+impl Strike {
+    /// Get current exchange rates
+    ///
+    /// Pairs in currencies this crate does not know are skipped
+    pub async fn get_rates_ticker(&self) -> Result<Vec<ConversionRate>> {
+        let url = self.url(&["v1", "rates", "ticker"])?;
+
+        let res = self.make_get(url).await?;
+
+        let pairs: Vec<Value> = match serde_json::from_value(res.clone()) {
+            Ok(pairs) => pairs,
+            Err(_) => {
+                log::error!("Api error response on rates ticker");
+                log::error!("{}", res);
+                bail!("Could not get rates ticker")
+            }
+        };
+
+        Ok(pairs
+            .into_iter()
+            .filter_map(|pair| match serde_json::from_value(pair.clone()) {
+                Ok(rate) => Some(rate),
+                Err(_) => {
+                    log::debug!("Skipping unsupported rate: {}", pair);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+/// Rates and when they were fetched
+type CachedRates = Option<(Instant, Vec<ConversionRate>)>;
+
+/// Rates ticker cache
+///
+/// Clones share the cached rates. Concurrent callers wait on a single
+/// refresh rather than each calling the api.
+#[derive(Debug, Clone)]
+pub struct RatesCache {
+    strike: Strike,
+    ttl: Duration,
+    rates: Arc<Mutex<CachedRates>>,
+}
+
+impl RatesCache {
+    /// Create cache that refreshes rates older than `ttl`
+    pub fn new(strike: Strike, ttl: Duration) -> Self {
+        Self {
+            strike,
+            ttl,
+            rates: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Get rates, from the cache if fresh
+    pub async fn get_rates(&self) -> Result<Vec<ConversionRate>> {
+        let mut rates = self.rates.lock().await;
+
+        if let Some((fetched, cached)) = rates.as_ref() {
+            if fetched.elapsed() < self.ttl {
+                return Ok(cached.clone());
+            }
+        }
+
+        let fresh = self.strike.get_rates_ticker().await?;
+        *rates = Some((Instant::now(), fresh.clone()));
+
+        Ok(fresh)
+    }
+
+    /// Get rate from `source` to `target`
+    ///
+    /// Falls back to inverting the `target` to `source` rate
+    pub async fn get_rate(&self, source: &Currency, target: &Currency) -> Result<ConversionRate> {
+        let rates = self.get_rates().await?;
+
+        if let Some(rate) = rates
+            .iter()
+            .find(|rate| &rate.source_currency == source && &rate.target_currency == target)
+        {
+            return Ok(rate.clone());
+        }
+
+        match rates
+            .iter()
+            .find(|rate| &rate.source_currency == target && &rate.target_currency == source)
+        {
+            Some(rate) if rate.amount > 0.0 => Ok(ConversionRate {
+                amount: 1.0 / rate.amount,
+                source_currency: source.clone(),
+                target_currency: target.clone(),
+            }),
+            _ => bail!("No rate from {} to {}", source, target),
+        }
+    }
+
+    /// Drop cached rates so the next call fetches
+    pub async fn invalidate(&self) {
+        *self.rates.lock().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    fn ticker(amount: &str) -> Value {
+        json!([
+            { "amount": amount, "sourceCurrency": "BTC", "targetCurrency": "USD" },
+            { "amount": "1.00", "sourceCurrency": "XYZ", "targetCurrency": "USD" }
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_rates_refreshed_after_ttl() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, ticker("50000.00"))
+            .respond_json(200, ticker("60000.00"));
+
+        let cache = RatesCache::new(fake.client(), Duration::from_secs(3600));
+        assert_eq!(cache.get_rates().await.unwrap()[0].amount, 50000.0);
+        assert_eq!(cache.clone().get_rates().await.unwrap()[0].amount, 50000.0);
+        assert_eq!(fake.calls(), ["GET /v1/rates/ticker"]);
+
+        cache.invalidate().await;
+        assert_eq!(cache.get_rates().await.unwrap()[0].amount, 60000.0);
+        assert_eq!(fake.calls().len(), 2);
+
+        // Expired rates are fetched again
+        let fake = FakeTransport::default();
+        fake.respond_json(200, ticker("50000.00"))
+            .respond_json(200, ticker("60000.00"));
+
+        let cache = RatesCache::new(fake.client(), Duration::ZERO);
+        assert_eq!(cache.get_rates().await.unwrap()[0].amount, 50000.0);
+        assert_eq!(cache.get_rates().await.unwrap()[0].amount, 60000.0);
+        assert_eq!(fake.calls().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_rate_inverts() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, ticker("50000.00"));
+
+        let cache = RatesCache::new(fake.client(), Duration::from_secs(3600));
+
+        let rate = cache
+            .get_rate(&Currency::BTC, &Currency::USD)
+            .await
+            .unwrap();
+        assert_eq!(rate.amount, 50000.0);
+
+        let rate = cache
+            .get_rate(&Currency::USD, &Currency::BTC)
+            .await
+            .unwrap();
+        assert_eq!(rate.source_currency, Currency::USD);
+        assert_eq!(rate.target_currency, Currency::BTC);
+        assert_eq!(rate.amount, 1.0 / 50000.0);
+
+        assert!(cache
+            .get_rate(&Currency::EUR, &Currency::BTC)
+            .await
+            .is_err());
+        assert_eq!(fake.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_zero_rate_not_inverted() {
+        let fake = FakeTransport::default();
+        fake.respond_json(200, ticker("0.00"));
+
+        let cache = RatesCache::new(fake.client(), Duration::from_secs(3600));
+
+        assert!(cache
+            .get_rate(&Currency::USD, &Currency::BTC)
+            .await
+            .is_err());
+    }
+}
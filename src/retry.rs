@@ -0,0 +1,157 @@
+//! Retry policy
+//!
+//! `GET` and `DELETE` requests, and `POST` requests carrying an
+//! [`IDEMPOTENCY_KEY_HEADER`], are retried on timeouts, connection errors,
+//! `429` and `5xx` responses. Every `POST` the client sends carries a key.
+//!
+//! Executions of payments, payouts and exchanges, such as
+//! [`Strike::pay_quote`], are only sent again after a `429`, which Strike did
+//! not act on. Repeat them with the returned key, or opt in to retries for a
+//! call with [`Strike::with_execution_retries`].
+
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{Method, StatusCode};
+
+use crate::Strike;
+
+/// Header marking a request as safe to repeat
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Retry policy for api requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Max attempts including the first, `1` disables retries
+    pub max_attempts: u32,
+    /// Backoff before the first retry
+    pub initial_backoff: Duration,
+    /// Max backoff between retries
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(0),
+            max_backoff: Duration::from_millis(0),
+        }
+    }
+
+    /// Policy with `max_attempts` and exponential backoff from
+    /// `initial_backoff`, capped at `max_backoff`
+    pub fn exponential(
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Backoff before retry after `attempt`, with jitter
+    ///
+    /// Doubles from the initial backoff each attempt up to the max, then a
+    /// random delay of up to half of it is taken off
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+
+        let half = backoff / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+
+        half + Duration::from_millis(jitter)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, backing off from 250ms up to 4s
+    fn default() -> Self {
+        Self::exponential(3, Duration::from_millis(250), Duration::from_secs(4))
+    }
+}
+
+impl Strike {
+    /// Client that retries executions of payments, payouts and exchanges
+    ///
+    /// Only use it for a call whose execution is safe to repeat, e.g.
+    /// `strike.with_execution_retries().pay_quote(id)`, other calls are
+    /// retried either way.
+    pub fn with_execution_retries(&self) -> Self {
+        Self {
+            retry_executions: true,
+            ..self.clone()
+        }
+    }
+}
+
+pub(crate) fn is_idempotent(request: &http::Request<Vec<u8>>) -> bool {
+    match *request.method() {
+        Method::GET | Method::DELETE => true,
+        // Every patch executes a payment, payout or exchange, whether Strike
+        // acts on a repeated key for them once is not relied on
+        Method::PATCH => false,
+        _ => request.headers().contains_key(IDEMPOTENCY_KEY_HEADER),
+    }
+}
+
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    #[test]
+    fn test_backoff() {
+        let policy =
+            RetryPolicy::exponential(5, Duration::from_millis(100), Duration::from_millis(300));
+
+        for _ in 0..20 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+            let capped = policy.backoff(4);
+            assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+        }
+
+        assert_eq!(RetryPolicy::none().backoff(1), Duration::from_millis(0));
+    }
+
+    #[tokio::test]
+    async fn test_execution_not_retried() {
+        let fake = FakeTransport::default();
+        fake.respond(503, "").respond(503, "");
+
+        assert!(fake.client().pay_quote("quote-1").await.is_err());
+        assert_eq!(fake.calls(), ["PATCH /v1/payment-quotes/quote-1/execute"]);
+
+        // Opting in retries the execution, `{}` then fails to parse
+        assert!(fake
+            .client()
+            .with_execution_retries()
+            .pay_quote("quote-1")
+            .await
+            .is_err());
+        assert_eq!(fake.calls().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_execution_retried_after_429() {
+        let fake = FakeTransport::default();
+        fake.respond(429, "");
+
+        assert!(fake.client().pay_quote("quote-1").await.is_err());
+        assert_eq!(fake.calls().len(), 2);
+    }
+}
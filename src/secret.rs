@@ -0,0 +1,92 @@
+//! Secrets
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+/// Secret string, such as the api key or webhook secret
+///
+/// Prints as `[REDACTED]` and is zeroized on drop
+#[derive(Clone, Default, Hash, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Create secret
+    pub fn new<S>(secret: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(secret.into())
+    }
+
+    /// Secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacted() {
+        let secret = Secret::new("hunter2");
+
+        assert_eq!(format!("{secret:?}"), "[REDACTED]");
+        assert_eq!(secret.expose(), "hunter2");
+        assert_eq!(serde_json::to_value(&secret).unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn test_client_debug_redacted() {
+        let builder = crate::Strike::builder("api-key-hunter2");
+        assert!(!format!("{builder:?}").contains("hunter2"));
+
+        let strike = builder.build().unwrap();
+        assert!(!format!("{strike:?}").contains("hunter2"));
+    }
+}
@@ -0,0 +1,324 @@
+//! Request telemetry
+//!
+//! With the `tracing` feature each api call runs in a `strike.request` span
+//! carrying the method, endpoint, status, latency, attempts and Strike's
+//! request id. With the `metrics` feature each call increments
+//! `strike_requests_total` and records `strike_request_duration_seconds`,
+//! labelled by method, endpoint and status.
+//!
+//! Endpoints are path templates relative to the base url, such as
+//! `/v1/invoices/{id}/quote`, so ids do not end up in labels. The
+//! `Authorization` header and secrets are redacted before anything is logged.
+
+use std::time::Instant;
+
+use http::HeaderMap;
+use reqwest::Method;
+use serde_json::Value;
+
+use crate::TransportError;
+
+/// Placeholder for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+// Headers whose values are never logged
+const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "cookie", "set-cookie"];
+
+// Json fields whose values are never logged
+const SENSITIVE_FIELDS: [&str; 4] = ["secret", "apiKey", "authorization", "webhookSecret"];
+
+// Segments of api routes, any other segment is an id or handle
+const ROUTE_SEGMENTS: [&str; 22] = [
+    "v1",
+    "accounts",
+    "bank",
+    "currency-exchange-quotes",
+    "deposits",
+    "events",
+    "execute",
+    "handle",
+    "initiate",
+    "invoices",
+    "lightning",
+    "lnurl",
+    "payment-methods",
+    "payment-quotes",
+    "payments",
+    "payout-originators",
+    "payouts",
+    "profile",
+    "quote",
+    "rates",
+    "subscriptions",
+    "ticker",
+];
+
+/// Path template of `path` relative to `base_path`, with ids and handles
+/// replaced by placeholders
+pub fn endpoint(base_path: &str, path: &str) -> String {
+    let relative = path
+        .strip_prefix(base_path.trim_end_matches('/'))
+        .filter(|relative| relative.is_empty() || relative.starts_with('/'))
+        .unwrap_or(path);
+
+    let mut template = String::new();
+    let mut after_handle = false;
+
+    for segment in relative.split('/').filter(|s| !s.is_empty()) {
+        template.push('/');
+
+        match (after_handle, ROUTE_SEGMENTS.contains(&segment)) {
+            (true, _) => template.push_str("{handle}"),
+            (false, true) => template.push_str(segment),
+            (false, false) => template.push_str("{id}"),
+        }
+
+        after_handle = segment == "handle";
+    }
+
+    template
+}
+
+/// Headers as `name: value` pairs with sensitive values redacted
+pub fn redact_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match SENSITIVE_HEADERS.contains(&name.as_str()) {
+                true => REDACTED,
+                false => value.to_str().unwrap_or("<binary>"),
+            };
+            format!("{name}: {value}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Copy of `value` with secret fields redacted at any depth
+pub fn redact_json(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(
+                    |(key, value)| match SENSITIVE_FIELDS.contains(&key.as_str()) {
+                        true => (key.clone(), Value::String(REDACTED.to_string())),
+                        false => (key.clone(), redact_json(value)),
+                    },
+                )
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(redact_json).collect()),
+        value => value.clone(),
+    }
+}
+
+// Strike's id of the request, for support tickets
+#[cfg(feature = "tracing")]
+fn request_id(headers: &HeaderMap) -> Option<&str> {
+    ["request-id", "x-request-id"]
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|value| value.to_str().ok())
+}
+
+// Telemetry of one api call, spanning all of its attempts
+pub(crate) struct Call {
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    method: Method,
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    endpoint: String,
+    started: Instant,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Call {
+    pub(crate) fn start(request: &http::Request<Vec<u8>>, base_path: &str) -> Self {
+        let method = request.method().clone();
+        let endpoint = endpoint(base_path, request.uri().path());
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "strike.request",
+                method = %method,
+                endpoint = %endpoint,
+                status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                attempts = tracing::field::Empty,
+                request_id = tracing::field::Empty,
+            ),
+            method,
+            endpoint,
+            started: Instant::now(),
+        }
+    }
+
+    // Run `future` inside the span of the call
+    pub(crate) async fn instrument<F>(&self, future: F) -> F::Output
+    where
+        F: std::future::Future,
+    {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+            future.instrument(self.span.clone()).await
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        future.await
+    }
+
+    // Trace an attempt, with sensitive headers redacted
+    pub(crate) fn attempt(&self, request: &http::Request<Vec<u8>>) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            parent: &self.span,
+            headers = %redact_headers(request.headers()),
+            "Sending request"
+        );
+
+        #[cfg(not(feature = "tracing"))]
+        let _ = request;
+    }
+
+    pub(crate) fn finish(
+        self,
+        attempts: u32,
+        res: &Result<http::Response<Vec<u8>>, TransportError>,
+    ) {
+        let latency = self.started.elapsed();
+        let status = match res {
+            Ok(res) => res.status().as_str().to_string(),
+            Err(_) => "error".to_string(),
+        };
+
+        #[cfg(feature = "tracing")]
+        self.trace(attempts, latency, &status, res);
+
+        #[cfg(feature = "metrics")]
+        self.measure(latency, status);
+
+        #[cfg(not(feature = "tracing"))]
+        let _ = (attempts, res);
+        #[cfg(not(feature = "metrics"))]
+        let _ = (latency, status);
+    }
+
+    #[cfg(feature = "tracing")]
+    fn trace(
+        &self,
+        attempts: u32,
+        latency: std::time::Duration,
+        status: &str,
+        res: &Result<http::Response<Vec<u8>>, TransportError>,
+    ) {
+        let span = &self.span;
+        span.record("status", status);
+        span.record("latency_ms", latency.as_millis() as u64);
+        span.record("attempts", attempts);
+
+        match res {
+            Ok(res) => {
+                if let Some(request_id) = request_id(res.headers()) {
+                    span.record("request_id", request_id);
+                }
+
+                match res.status().is_success() {
+                    true => tracing::debug!(parent: span, "Strike api call"),
+                    false => tracing::warn!(parent: span, "Strike api call failed"),
+                }
+            }
+            Err(err) => tracing::warn!(parent: span, error = %err, "Strike api call failed"),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn measure(&self, latency: std::time::Duration, status: String) {
+        let method = self.method.to_string();
+
+        metrics::counter!(
+            "strike_requests_total",
+            "method" => method.clone(),
+            "endpoint" => self.endpoint.clone(),
+            "status" => status
+        )
+        .increment(1);
+
+        metrics::histogram!(
+            "strike_request_duration_seconds",
+            "method" => method,
+            "endpoint" => self.endpoint.clone()
+        )
+        .record(latency.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(
+            endpoint(
+                "/",
+                "/v1/invoices/1b4ed7a2-0c5d-4c6b-9b49-9e4c1f1f1e7b/quote"
+            ),
+            "/v1/invoices/{id}/quote"
+        );
+        assert_eq!(
+            endpoint("/", "/v1/accounts/handle/satoshi/profile"),
+            "/v1/accounts/handle/{handle}/profile"
+        );
+        assert_eq!(
+            endpoint("/", "/v1/payment-quotes/lightning"),
+            "/v1/payment-quotes/lightning"
+        );
+
+        // Lowercase ids are not taken for routes
+        assert_eq!(
+            endpoint("/", "/v1/payment-methods/bank/pm-abc"),
+            "/v1/payment-methods/bank/{id}"
+        );
+        assert_eq!(endpoint("/", "/v1/events/evt"), "/v1/events/{id}");
+    }
+
+    #[test]
+    fn test_endpoint_base_path() {
+        for base_path in ["/strike", "/strike/"] {
+            assert_eq!(
+                endpoint(base_path, "/strike/v1/invoices/inv-1"),
+                "/v1/invoices/{id}"
+            );
+        }
+
+        // Only whole segments of the base path are stripped
+        assert_eq!(
+            endpoint("/strike", "/striker/v1/invoices"),
+            "/{id}/v1/invoices"
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer secret-key".parse().unwrap());
+        headers.insert("accept", "application/json".parse().unwrap());
+
+        let redacted = redact_headers(&headers);
+        assert!(redacted.contains("authorization: [REDACTED]"));
+        assert!(redacted.contains("accept: application/json"));
+        assert!(!redacted.contains("secret-key"));
+
+        let value = serde_json::json!({
+            "webhookUrl": "https://example.com",
+            "secret": "hunter2",
+            "items": [{ "apiKey": "abc" }]
+        });
+        let redacted = redact_json(&value);
+        assert_eq!(redacted["secret"], REDACTED);
+        assert_eq!(redacted["items"][0]["apiKey"], REDACTED);
+        assert_eq!(redacted["webhookUrl"], "https://example.com");
+    }
+}
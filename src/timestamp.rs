@@ -0,0 +1,229 @@
+//! Timestamp
+//!
+//! Minimal RFC 3339 parsing and formatting for the timestamps returned by
+//! Strike, e.g. `2023-03-16T15:30:37.4390395+00:00`
+
+use core::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Timestamp error
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Timestamp is not RFC 3339
+    InvalidFormat,
+    /// Timestamp is before the unix epoch
+    BeforeEpoch,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFormat => write!(f, "Invalid timestamp format"),
+            Self::BeforeEpoch => write!(f, "Timestamp is before unix epoch"),
+        }
+    }
+}
+
+fn digits(s: &[u8], start: usize, len: usize) -> Result<i64, Error> {
+    let slice = s.get(start..start + len).ok_or(Error::InvalidFormat)?;
+
+    slice.iter().try_fold(0i64, |acc, c| match c {
+        b'0'..=b'9' => Ok(acc * 10 + (c - b'0') as i64),
+        _ => Err(Error::InvalidFormat),
+    })
+}
+
+fn expect(s: &[u8], index: usize, chars: &[u8]) -> Result<(), Error> {
+    match s.get(index) {
+        Some(c) if chars.contains(c) => Ok(()),
+        _ => Err(Error::InvalidFormat),
+    }
+}
+
+// Days since 1970-01-01 of a proleptic gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = (month + 9) % 12;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Proleptic gregorian date of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Format as RFC 3339 in UTC with millisecond precision
+#[cfg_attr(not(feature = "mock"), allow(dead_code))]
+pub fn format(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    format_with_fraction(since_epoch, &format!(".{:03}", since_epoch.subsec_millis()))
+}
+
+/// Format as RFC 3339 in UTC without losing precision
+///
+/// Fractional seconds are left out when zero
+pub fn format_precise(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    let fraction = match since_epoch.subsec_nanos() {
+        0 => String::new(),
+        nanos => format!(".{nanos:09}").trim_end_matches('0').to_string(),
+    };
+
+    format_with_fraction(since_epoch, &fraction)
+}
+
+fn format_with_fraction(since_epoch: Duration, fraction: &str) -> String {
+    let secs = since_epoch.as_secs() as i64;
+
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let secs_of_day = secs % 86_400;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{fraction}+00:00",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+/// Parse RFC 3339 timestamp
+pub fn parse(timestamp: &str) -> Result<SystemTime, Error> {
+    let s = timestamp.as_bytes();
+
+    let year = digits(s, 0, 4)?;
+    expect(s, 4, b"-")?;
+    let month = digits(s, 5, 2)?;
+    expect(s, 7, b"-")?;
+    let day = digits(s, 8, 2)?;
+    expect(s, 10, b"Tt ")?;
+    let hour = digits(s, 11, 2)?;
+    expect(s, 13, b":")?;
+    let minute = digits(s, 14, 2)?;
+    expect(s, 16, b":")?;
+    let second = digits(s, 17, 2)?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return Err(Error::InvalidFormat);
+    }
+    // Allow leap seconds
+    if second > 60 {
+        return Err(Error::InvalidFormat);
+    }
+
+    let mut index = 19;
+    let mut nanos = 0u32;
+    if s.get(index) == Some(&b'.') {
+        index += 1;
+        let start = index;
+        while let Some(c @ b'0'..=b'9') = s.get(index) {
+            if index - start < 9 {
+                nanos = nanos * 10 + (c - b'0') as u32;
+            }
+            index += 1;
+        }
+        if index == start {
+            return Err(Error::InvalidFormat);
+        }
+        for _ in (index - start)..9 {
+            nanos *= 10;
+        }
+    }
+
+    let offset = match s.get(index) {
+        Some(b'Z' | b'z') if s.len() == index + 1 => 0,
+        Some(sign @ (b'+' | b'-')) if s.len() == index + 6 => {
+            let hours = digits(s, index + 1, 2)?;
+            expect(s, index + 3, b":")?;
+            let minutes = digits(s, index + 4, 2)?;
+            let offset = hours * 3600 + minutes * 60;
+            match sign {
+                b'-' => -offset,
+                _ => offset,
+            }
+        }
+        _ => return Err(Error::InvalidFormat),
+    };
+
+    let secs =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+
+    if secs < 0 {
+        return Err(Error::BeforeEpoch);
+    }
+
+    Ok(UNIX_EPOCH + Duration::new(secs as u64, nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("2023-03-16T15:30:37.4390395+00:00"),
+            Ok(UNIX_EPOCH + Duration::new(1_678_980_637, 439_039_500))
+        );
+        assert_eq!(
+            parse("1970-01-01T00:00:00Z"),
+            Ok(UNIX_EPOCH + Duration::from_secs(0))
+        );
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(
+            parse("2024-02-29T01:00:00+01:30"),
+            parse("2024-02-28T23:30:00Z")
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let time = UNIX_EPOCH + Duration::new(1_678_980_637, 439_039_500);
+
+        assert_eq!(format(time), "2023-03-16T15:30:37.439+00:00");
+        assert_eq!(
+            parse(&format(time)),
+            Ok(UNIX_EPOCH + Duration::from_millis(1_678_980_637_439))
+        );
+        assert_eq!(format(UNIX_EPOCH), "1970-01-01T00:00:00.000+00:00");
+    }
+
+    #[test]
+    fn test_format_precise() {
+        let time = UNIX_EPOCH + Duration::new(1_678_980_637, 439_039_500);
+
+        assert_eq!(format_precise(time), "2023-03-16T15:30:37.4390395+00:00");
+        assert_eq!(parse(&format_precise(time)), Ok(time));
+        assert_eq!(format_precise(UNIX_EPOCH), "1970-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse("2023-03-16"), Err(Error::InvalidFormat));
+        assert_eq!(parse("2023-13-16T15:30:37Z"), Err(Error::InvalidFormat));
+        assert_eq!(parse("2023-03-16T15:30:37"), Err(Error::InvalidFormat));
+        assert_eq!(parse("1969-12-31T23:59:59Z"), Err(Error::BeforeEpoch));
+    }
+}
@@ -0,0 +1,300 @@
+//! HTTP transport
+//!
+//! Requests to Strike are sent through a [`Transport`]. [`ReqwestTransport`]
+//! is used unless another is set with
+//! [`StrikeBuilder::transport`](crate::StrikeBuilder::transport), e.g. to
+//! record and replay responses in tests, audit requests or use a different
+//! http stack.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use thiserror::Error;
+
+/// Boxed error of a [`Transport`]
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Transport error
+#[derive(Debug, Error)]
+pub enum TransportError {
+    /// Request timed out
+    #[error("Request timed out: {0}")]
+    Timeout(BoxError),
+    /// Connection failed or was dropped before a response
+    #[error("Network error: {0}")]
+    Network(BoxError),
+    /// Other error
+    #[error(transparent)]
+    Other(BoxError),
+}
+
+impl TransportError {
+    /// Whether a request that is safe to repeat may be retried
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Timeout(_) | Self::Network(_))
+    }
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(err: reqwest::Error) -> Self {
+        match err {
+            err if err.is_timeout() => Self::Timeout(err.into()),
+            err if err.is_connect() || err.is_request() => Self::Network(err.into()),
+            err => Self::Other(err.into()),
+        }
+    }
+}
+
+impl From<http::Error> for TransportError {
+    fn from(err: http::Error) -> Self {
+        Self::Other(err.into())
+    }
+}
+
+/// Sends requests to Strike
+///
+/// Requests carry the full url, auth and idempotency headers. Retries and
+/// throttling happen before the transport, each attempt is a separate call.
+#[async_trait]
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Send `request` and read the whole response body
+    async fn send(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Vec<u8>>, TransportError>;
+}
+
+/// [`Transport`] over a [`reqwest::Client`]
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    /// Transport using `client`
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Vec<u8>>, TransportError> {
+        let request = reqwest::Request::try_from(request)?;
+
+        let res = self.client.execute(request).await?;
+
+        let mut response = http::Response::builder()
+            .status(res.status())
+            .version(res.version());
+        if let Some(headers) = response.headers_mut() {
+            *headers = res.headers().clone();
+        }
+
+        let body = res.bytes().await?;
+
+        Ok(response.body(body.to_vec())?)
+    }
+}
+
+// `http::Request` is not `Clone`, copy all but the extensions for a retry
+pub(crate) fn clone_request(request: &http::Request<Vec<u8>>) -> http::Request<Vec<u8>> {
+    let mut clone = http::Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+
+    clone
+}
+
+#[cfg(test)]
+pub(crate) mod fake {
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{RetryPolicy, Strike};
+
+    // Transport for tests, answering queued responses in order and `200 {}`
+    // once they run out
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct FakeTransport {
+        responses: Arc<Mutex<VecDeque<http::Response<Vec<u8>>>>>,
+        requests: Arc<Mutex<Vec<http::Request<Vec<u8>>>>>,
+    }
+
+    impl FakeTransport {
+        // Queue a response
+        pub(crate) fn respond(&self, status: u16, body: &str) -> &Self {
+            self.respond_with_headers(status, &[], body)
+        }
+
+        // Queue a response with extra headers
+        pub(crate) fn respond_with_headers(
+            &self,
+            status: u16,
+            headers: &[(&str, &str)],
+            body: &str,
+        ) -> &Self {
+            let mut response = http::Response::builder()
+                .status(status)
+                .header("content-type", "application/json");
+            for (name, value) in headers {
+                response = response.header(*name, *value);
+            }
+
+            self.responses
+                .lock()
+                .unwrap()
+                .push_back(response.body(body.as_bytes().to_vec()).unwrap());
+            self
+        }
+
+        // Queue a json response
+        pub(crate) fn respond_json(&self, status: u16, body: serde_json::Value) -> &Self {
+            self.respond(status, &body.to_string())
+        }
+
+        // Requests sent so far
+        pub(crate) fn requests(&self) -> Vec<http::Request<Vec<u8>>> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .map(clone_request)
+                .collect()
+        }
+
+        // `METHOD path?query` of each request sent so far
+        pub(crate) fn calls(&self) -> Vec<String> {
+            self.requests()
+                .iter()
+                .map(|request| {
+                    let path = request
+                        .uri()
+                        .path_and_query()
+                        .map(|path| path.as_str())
+                        .unwrap_or_default();
+                    format!("{} {}", request.method(), path)
+                })
+                .collect()
+        }
+
+        // Decoded query parameters of each request sent so far
+        pub(crate) fn queries(&self) -> Vec<HashMap<String, String>> {
+            self.requests()
+                .iter()
+                .map(|request| {
+                    let url = reqwest::Url::parse(&request.uri().to_string()).unwrap();
+                    url.query_pairs().into_owned().collect()
+                })
+                .collect()
+        }
+
+        // JSON body of each request sent so far, null when empty
+        pub(crate) fn bodies(&self) -> Vec<serde_json::Value> {
+            self.requests()
+                .iter()
+                .map(|request| serde_json::from_slice(request.body()).unwrap_or_default())
+                .collect()
+        }
+
+        // Client sending through this transport with millisecond retries
+        pub(crate) fn client(&self) -> Strike {
+            Strike::builder("api-key")
+                .transport(self.clone())
+                .retry_policy(RetryPolicy::exponential(
+                    3,
+                    Duration::from_millis(1),
+                    Duration::from_millis(1),
+                ))
+                .build()
+                .unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl Transport for FakeTransport {
+        async fn send(
+            &self,
+            request: http::Request<Vec<u8>>,
+        ) -> Result<http::Response<Vec<u8>>, TransportError> {
+            self.requests.lock().unwrap().push(request);
+            let response = self.responses.lock().unwrap().pop_front();
+
+            match response {
+                Some(response) => Ok(response),
+                None => Ok(http::Response::builder()
+                    .status(200)
+                    .header("content-type", "application/json")
+                    .body(b"{}".to_vec())?),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{RetryPolicy, Strike};
+
+    // Replays `statuses` in order and records each request
+    #[derive(Debug, Default)]
+    struct Replay {
+        statuses: Mutex<Vec<u16>>,
+        requests: Arc<Mutex<Vec<http::Request<Vec<u8>>>>>,
+    }
+
+    #[async_trait]
+    impl Transport for Replay {
+        async fn send(
+            &self,
+            request: http::Request<Vec<u8>>,
+        ) -> Result<http::Response<Vec<u8>>, TransportError> {
+            self.requests.lock().unwrap().push(request);
+            let status = self.statuses.lock().unwrap().remove(0);
+
+            Ok(http::Response::builder()
+                .status(status)
+                .body(br#"{"ok":true}"#.to_vec())?)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport() {
+        let replay = Replay {
+            statuses: Mutex::new(vec![503, 200]),
+            ..Default::default()
+        };
+        let requests = replay.requests.clone();
+
+        let strike = Strike::builder("api-key")
+            .transport(replay)
+            .retry_policy(RetryPolicy::exponential(
+                3,
+                Duration::from_millis(1),
+                Duration::from_millis(1),
+            ))
+            .build()
+            .unwrap();
+
+        let url = strike.url(&["v1", "balances"]).unwrap();
+        let res = strike.make_get(url).await.unwrap();
+        assert_eq!(res["ok"], true);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].uri(), "https://api.strike.me/v1/balances");
+        assert_eq!(requests[1].headers()["Authorization"], "Bearer api-key");
+    }
+}
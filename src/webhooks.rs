@@ -1,6 +1,8 @@
 //! Strike Webhooks
 
-use anyhow::anyhow;
+use std::fmt;
+use std::sync::Arc;
+
 use axum::body::{self, BoxBody, Full};
 use axum::extract::State;
 use axum::http::request::Request;
@@ -9,21 +11,41 @@ use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Response};
 use axum::routing::post;
 use axum::{Json, Router};
-use ring::hmac::{self, Key};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tower::ServiceBuilder;
 use tower_http::ServiceBuilderExt;
 
-use crate::{hex, Strike};
+#[cfg(any(
+    feature = "axum-adapter",
+    feature = "actix-adapter",
+    feature = "tower-adapter"
+))]
+pub mod adapters;
+mod handler;
+mod replay;
+mod verify;
+
+pub use self::handler::{ChannelHandler, DeliveryMode, HandlerResult, WebhookHandler};
+use self::replay::Replay;
+pub use self::replay::{MemorySeenEventStore, ReplayProtection, SeenEventStore};
+pub use self::verify::{
+    sign_webhook, verify_signature, verify_webhook, WebhookError, WebhookHeaders, WebhookSecret,
+    SIGNATURE_HEADER,
+};
+pub use crate::events::{WebHookData, WebhookEvent};
+use crate::telemetry::redact_json;
+use crate::{Secret, Strike};
 
 /// Webhook state
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct WebhookState {
     /// Webhook secret
-    pub webhook_secret: String,
-    /// Sender
-    pub sender: tokio::sync::mpsc::Sender<String>,
+    pub webhook_secret: WebhookSecret,
+    /// Event handler
+    pub handler: Arc<dyn WebhookHandler>,
+    /// Replay protection
+    pub replay_protection: ReplayProtection,
 }
 
 /// Webhook data
@@ -35,7 +57,7 @@ pub struct WebhookRequest {
     /// Webhook version
     pub webhook_version: String,
     /// Secret
-    pub secret: String,
+    pub secret: Secret,
     /// Enabled
     pub enabled: bool,
     /// Event Types
@@ -60,18 +82,62 @@ pub struct WebhookInfoResponse {
 
 impl Strike {
     /// Create invoice webhook
+    ///
+    /// Sends the entity id of each event on `sender` with a
+    /// [`ChannelHandler`]. Uses the default [`ReplayProtection`], keeping seen
+    /// event ids in memory.
     pub async fn create_invoice_webhook_router(
         &self,
         webhook_endpoint: &str,
         sender: tokio::sync::mpsc::Sender<String>,
     ) -> anyhow::Result<Router> {
-        let state = WebhookState {
+        self.create_invoice_webhook_router_with_replay_protection(
+            webhook_endpoint,
             sender,
-            webhook_secret: self.webhook_secret.clone(),
+            ReplayProtection::default(),
+        )
+        .await
+    }
+
+    /// Create invoice webhook with [`ReplayProtection`]
+    ///
+    /// Repeated event ids within the dedup window are acknowledged but not
+    /// forwarded, events older than the max event age are rejected.
+    pub async fn create_invoice_webhook_router_with_replay_protection(
+        &self,
+        webhook_endpoint: &str,
+        sender: tokio::sync::mpsc::Sender<String>,
+        replay_protection: ReplayProtection,
+    ) -> anyhow::Result<Router> {
+        self.create_webhook_router(
+            webhook_endpoint,
+            ChannelHandler::new(sender),
+            replay_protection,
+        )
+        .await
+    }
+
+    /// Create webhook with a [`WebhookHandler`]
+    ///
+    /// The handler decides whether each event was accepted, see
+    /// [`HandlerResult`] for the response Strike is sent.
+    pub async fn create_webhook_router<H>(
+        &self,
+        webhook_endpoint: &str,
+        handler: H,
+        replay_protection: ReplayProtection,
+    ) -> anyhow::Result<Router>
+    where
+        H: WebhookHandler + 'static,
+    {
+        let state = WebhookState {
+            webhook_secret: self.webhook_secret(),
+            handler: Arc::new(handler),
+            replay_protection,
         };
 
         let router = Router::new()
-            .route(webhook_endpoint, post(handle_invoice))
+            .route(webhook_endpoint, post(handle_event))
             .layer(ServiceBuilder::new().map_request_body(body::boxed).layer(
                 middleware::from_fn_with_state(state.clone(), verify_request_body),
             ))
@@ -80,9 +146,17 @@ impl Strike {
         Ok(router)
     }
 
+    /// Secret Strike signs webhook deliveries with
+    ///
+    /// Pass this to [`verify_webhook`] or one of the webhook adapters when not
+    /// using [`Strike::create_invoice_webhook_router`]
+    pub fn webhook_secret(&self) -> WebhookSecret {
+        WebhookSecret::new(self.webhook_secret.expose())
+    }
+
     /// Subscribe to invoice webhook
     pub async fn subscribe_to_invoice_webhook(&self, webhook_url: String) -> anyhow::Result<()> {
-        let url = self.base_url.join("/v1/subscriptions")?;
+        let url = self.url(&["v1", "subscriptions"])?;
 
         let subscription = WebhookRequest {
             webhook_url,
@@ -92,18 +166,24 @@ impl Strike {
             event_types: vec!["invoice.updated".to_string()],
         };
 
+        let idempotency_key = self.idempotency_key();
+
         let res = self
-            .make_post(url, Some(serde_json::to_value(subscription)?))
+            .make_post(
+                url,
+                Some(serde_json::to_value(subscription)?),
+                &idempotency_key,
+            )
             .await?;
 
-        log::debug!("Created Webhook subscription: {}", res);
+        log::debug!("Created Webhook subscription: {}", redact_json(&res));
 
         Ok(())
     }
 
     /// Get current subscriptions
     pub async fn get_current_subscriptions(&self) -> anyhow::Result<Vec<WebhookInfoResponse>> {
-        let url = self.base_url.join("/v1/subscriptions")?;
+        let url = self.url(&["v1", "subscriptions"])?;
 
         let res = self.make_get(url).await?;
 
@@ -114,9 +194,7 @@ impl Strike {
 
     /// Delete subscription
     pub async fn delete_subscription(&self, webhook_id: &str) -> anyhow::Result<()> {
-        let url = self
-            .base_url
-            .join(&format!("/v1/subscriptions/{}", webhook_id))?;
+        let url = self.url(&["v1", "subscriptions", webhook_id])?;
 
         self.make_delete(url).await
     }
@@ -134,10 +212,10 @@ async fn verify_request_body(
 }
 
 // take the request apart, buffer the body,
-// veridy signature, then put the request back together
+// verify signature, then put the request back together
 async fn buffer_request_body(
     request: Request<BoxBody>,
-    secret: &str,
+    secret: &WebhookSecret,
 ) -> Result<Request<BoxBody>, Response> {
     let (parts, body) = request.into_parts();
 
@@ -145,95 +223,153 @@ async fn buffer_request_body(
         .await
         .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response())?;
 
-    let headers = parts.headers.clone();
-
-    let signature = headers
-        .get("X-Webhook-Signature")
-        .ok_or_else(|| {
-            log::warn!("Post to webhook did not include signature");
-            StatusCode::UNAUTHORIZED.into_response()
-        })?
-        .to_str()
-        .map_err(|_| {
-            log::warn!("Webhook signature is not a valid string");
-            StatusCode::UNAUTHORIZED.into_response()
-        })?;
+    let signature = parts.headers.header(SIGNATURE_HEADER).ok_or_else(|| {
+        log::warn!("Post to webhook did not include signature");
+        StatusCode::UNAUTHORIZED.into_response()
+    })?;
 
-    verify_request_signature(signature, &bytes, secret.as_bytes())
-        .map_err(|_| StatusCode::UNAUTHORIZED)
-        .into_response();
+    verify_signature(signature, &bytes, secret.as_bytes())
+        .map_err(|_| StatusCode::UNAUTHORIZED.into_response())?;
 
     Ok(Request::from_parts(parts, body::boxed(Full::from(bytes))))
 }
 
-/// Webhook data
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct WebHookData {
-    /// Entity Id
-    entity_id: String,
-    /// Changes
-    changes: Vec<String>,
-}
-
-/// Webhook Response
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct WebHookResponse {
-    /// Webhook id
-    id: String,
-    /// Event type
-    event_type: String,
-    /// Webhook version
-    webhook_version: String,
-    /// Webhook data
-    data: WebHookData,
-    /// Created
-    created: String,
-    /// Delivery Success
-    delivery_success: Option<bool>,
-}
-// Function to compute HMAC SHA-256
-fn compute_hmac(content: &[u8], key: &Key) -> Vec<u8> {
-    let tag = hmac::sign(key, content);
-    tag.as_ref().to_vec()
-}
-
-// Function to verify request signature
-fn verify_request_signature(
-    request_signature: &str,
-    body: &[u8],
-    secret: &[u8],
-) -> anyhow::Result<()> {
-    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
-
-    let body = serde_json::from_slice(body)?;
-    let content_signature = compute_hmac(body, &key);
-
-    hmac::verify(&key, &hex::decode(request_signature)?, &content_signature).map_err(|_| {
-        log::warn!("Request did not have a valid signature");
-
-        anyhow!("Invalid signature")
-    })
+impl fmt::Debug for WebhookState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookState")
+            .field("webhook_secret", &self.webhook_secret)
+            .field("replay_protection", &self.replay_protection)
+            .finish_non_exhaustive()
+    }
 }
 
-async fn handle_invoice(
+async fn handle_event(
     State(state): State<WebhookState>,
     Json(payload): Json<Value>,
 ) -> Result<StatusCode, StatusCode> {
-    let webhook_response: WebHookResponse = serde_json::from_value(payload).map_err(|_err| {
+    let webhook_response: WebhookEvent = serde_json::from_value(payload).map_err(|_err| {
         log::warn!("Got an invalid payload on webhook");
 
         StatusCode::UNPROCESSABLE_ENTITY
     })?;
 
+    match state.replay_protection.check(&webhook_response).await {
+        Ok(Replay::New) => (),
+        Ok(Replay::Duplicate) => {
+            log::debug!("Dropping duplicate webhook event: {}", webhook_response.id);
+            return Ok(StatusCode::OK);
+        }
+        Ok(Replay::Stale) => {
+            log::warn!(
+                "Rejecting stale webhook event {} created {}",
+                webhook_response.id,
+                webhook_response.created
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Err(err) => {
+            log::error!("Could not check webhook event for replay: {}", err);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
     log::debug!(
         "Received webhook update for: {}",
         webhook_response.data.entity_id
     );
 
-    if let Err(err) = state.sender.send(webhook_response.data.entity_id).await {
-        log::warn!("Could not send on channel: {}", err);
+    let result = state.handler.handle(webhook_response.clone()).await;
+
+    if result == HandlerResult::Retry {
+        // Let Strike's redelivery through replay protection
+        if let Err(err) = state.replay_protection.forget(&webhook_response).await {
+            log::error!(
+                "Could not forget webhook event {}: {}",
+                webhook_response.id,
+                err
+            );
+        }
+    }
+
+    match result {
+        HandlerResult::Accepted => Ok(result.status_code()),
+        _ => Err(result.status_code()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, SystemTime};
+
+    use async_trait::async_trait;
+
+    use super::replay::tests::event;
+    use super::*;
+
+    // Asks for a retry on the first delivery and accepts the rest
+    #[derive(Debug, Default)]
+    struct RetryOnce {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl WebhookHandler for RetryOnce {
+        async fn handle(&self, _event: WebhookEvent) -> HandlerResult {
+            match self.calls.fetch_add(1, Ordering::SeqCst) {
+                0 => HandlerResult::Retry,
+                _ => HandlerResult::Accepted,
+            }
+        }
+    }
+
+    fn state(handler: Arc<RetryOnce>) -> WebhookState {
+        WebhookState {
+            webhook_secret: WebhookSecret::new("secret"),
+            handler,
+            replay_protection: ReplayProtection {
+                max_event_age: Some(Duration::from_secs(60)),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn deliver(state: &WebhookState, event: &WebhookEvent) -> StatusCode {
+        let payload = serde_json::to_value(event).unwrap();
+
+        match handle_event(State(state.clone()), Json(payload)).await {
+            Ok(status) | Err(status) => status,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_is_redelivered() {
+        let handler = Arc::new(RetryOnce::default());
+        let state = state(handler.clone());
+        let event = event("e1", SystemTime::now());
+
+        assert_eq!(
+            deliver(&state, &event).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(deliver(&state, &event).await, StatusCode::OK);
+
+        // Duplicates of the accepted event do not reach the handler
+        assert_eq!(deliver(&state, &event).await, StatusCode::OK);
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_and_invalid_events_rejected() {
+        let handler = Arc::new(RetryOnce::default());
+        let state = state(handler.clone());
+
+        let stale = event("e1", SystemTime::now() - Duration::from_secs(120));
+        assert_eq!(deliver(&state, &stale).await, StatusCode::BAD_REQUEST);
+
+        let res = handle_event(State(state), Json(serde_json::json!({ "id": 1 }))).await;
+        assert_eq!(res, Err(StatusCode::UNPROCESSABLE_ENTITY));
+
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 0);
     }
-    Ok(StatusCode::OK)
 }
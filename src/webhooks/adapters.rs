@@ -0,0 +1,30 @@
+//! Webhook verification adapters
+//!
+//! Each adapter is behind its own cargo feature and verifies deliveries with
+//! [`verify_webhook`](super::verify_webhook).
+
+/// Max webhook body size buffered for verification
+///
+/// Larger bodies are rejected with `413 Payload Too Large`
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+#[cfg(feature = "actix-adapter")]
+pub mod actix;
+#[cfg(feature = "axum-adapter")]
+pub mod axum;
+#[cfg(feature = "tower-adapter")]
+pub mod tower;
+
+// Error reading a body limited to `MAX_BODY_SIZE`
+#[cfg(any(feature = "axum-adapter", feature = "tower-adapter"))]
+fn body_error(err: &(dyn std::error::Error + 'static)) -> super::WebhookError {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if err.is::<http_body_util::LengthLimitError>() {
+            return super::WebhookError::BodyTooLarge;
+        }
+        source = err.source();
+    }
+
+    super::WebhookError::Body(err.to_string())
+}
@@ -0,0 +1,158 @@
+//! Actix web adapter
+//!
+//! ```
+//! use actix_web::{web, App};
+//! use strike_rs::webhooks::adapters::actix::VerifiedWebhook;
+//! # let strike = strike_rs::Strike::new("api-key", None).unwrap();
+//!
+//! async fn handle(VerifiedWebhook(event): VerifiedWebhook) -> &'static str {
+//!     println!("{}", event.data.entity_id);
+//!     "ok"
+//! }
+//!
+//! let app = App::new()
+//!     .app_data(web::Data::new(strike.webhook_secret()))
+//!     .route("/webhook", web::post().to(handle));
+//! # let _ = app;
+//! ```
+//!
+//! Bodies over [`MAX_BODY_SIZE`] are rejected with `413`, whatever the
+//! app's `PayloadConfig`.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use actix_web::dev::Payload;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::HeaderMap;
+use actix_web::http::StatusCode;
+use actix_web::web::{BytesMut, Data};
+use actix_web::{FromRequest, HttpRequest, ResponseError};
+use futures_util::StreamExt;
+
+pub use super::MAX_BODY_SIZE;
+use crate::webhooks::{verify_webhook, WebhookError, WebhookEvent, WebhookHeaders, WebhookSecret};
+
+impl WebhookHeaders for HeaderMap {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+impl ResponseError for WebhookError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(WebhookError::status_code(self)).unwrap_or(StatusCode::BAD_REQUEST)
+    }
+}
+
+/// Extractor of a verified webhook event
+///
+/// The app must provide a [`WebhookSecret`] as `web::Data`
+#[derive(Debug, Clone)]
+pub struct VerifiedWebhook(pub WebhookEvent);
+
+impl FromRequest for VerifiedWebhook {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(request: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let request = request.clone();
+        let payload = payload.take();
+
+        Box::pin(async move {
+            let secret = request
+                .app_data::<Data<WebhookSecret>>()
+                .ok_or_else(|| {
+                    log::error!("Webhook secret is not configured as app data");
+                    ErrorInternalServerError("Webhook secret not configured")
+                })?
+                .clone();
+
+            let bytes = read_body(payload).await?;
+
+            verify_webhook(request.headers(), &bytes, secret.as_bytes())
+                .map(Self)
+                .map_err(Into::into)
+        })
+    }
+}
+
+// Body buffered up to `MAX_BODY_SIZE`
+async fn read_body(mut payload: Payload) -> Result<BytesMut, actix_web::Error> {
+    let mut body = BytesMut::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err(WebhookError::BodyTooLarge.into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+    use crate::webhooks::verify::tests::BODY;
+    use crate::webhooks::{sign_webhook, SIGNATURE_HEADER};
+
+    async fn extract(
+        body: Vec<u8>,
+        signature: String,
+    ) -> Result<VerifiedWebhook, actix_web::Error> {
+        let (request, mut payload) = TestRequest::post()
+            .insert_header((SIGNATURE_HEADER, signature))
+            .app_data(Data::new(WebhookSecret::new("secret")))
+            .set_payload(body)
+            .to_http_parts();
+
+        VerifiedWebhook::from_request(&request, &mut payload).await
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let VerifiedWebhook(event) = extract(BODY.to_vec(), sign_webhook(BODY, b"secret"))
+            .await
+            .unwrap();
+        assert_eq!(event.data.entity_id, "i1");
+
+        // Over actix's default payload limit but within `MAX_BODY_SIZE`
+        let mut padded = BODY.to_vec();
+        padded.resize(512 * 1024, b' ');
+        let signature = sign_webhook(&padded, b"secret");
+        assert!(extract(padded, signature).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reject() {
+        let err = extract(BODY.to_vec(), sign_webhook(BODY, b"other"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let large = vec![b' '; MAX_BODY_SIZE + 1];
+        let signature = sign_webhook(&large, b"secret");
+        let err = extract(large, signature).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // Without the secret as app data
+        let (request, mut payload) = TestRequest::post().to_http_parts();
+        let err = VerifiedWebhook::from_request(&request, &mut payload)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
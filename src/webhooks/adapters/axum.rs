@@ -0,0 +1,98 @@
+//! Axum adapter
+//!
+//! ```
+//! # use axum_08 as axum;
+//! use axum::routing::post;
+//! use axum::Router;
+//! use strike_rs::webhooks::adapters::axum::VerifiedWebhook;
+//! # let strike = strike_rs::Strike::new("api-key", None).unwrap();
+//!
+//! async fn handle(VerifiedWebhook(event): VerifiedWebhook) {
+//!     println!("{}", event.data.entity_id);
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/webhook", post(handle))
+//!     .with_state(strike.webhook_secret());
+//! # let _ = app;
+//! ```
+
+use axum_08::body;
+use axum_08::extract::{FromRef, FromRequest, Request};
+use axum_08::http::StatusCode;
+use axum_08::response::{IntoResponse, Response};
+
+pub use super::MAX_BODY_SIZE;
+use crate::webhooks::{verify_webhook, WebhookError, WebhookEvent, WebhookSecret};
+
+/// Extractor of a verified webhook event
+///
+/// The router state must provide a [`WebhookSecret`] through [`FromRef`]
+#[derive(Debug, Clone)]
+pub struct VerifiedWebhook(pub WebhookEvent);
+
+impl<S> FromRequest<S> for VerifiedWebhook
+where
+    WebhookSecret: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let secret = WebhookSecret::from_ref(state);
+        let (parts, body) = request.into_parts();
+
+        let bytes = body::to_bytes(body, MAX_BODY_SIZE)
+            .await
+            .map_err(|err| rejection(super::body_error(&err)))?;
+
+        verify_webhook(&parts.headers, &bytes, secret.as_bytes())
+            .map(Self)
+            .map_err(rejection)
+    }
+}
+
+fn rejection(err: WebhookError) -> Response {
+    let status = StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::BAD_REQUEST);
+
+    (status, err.to_string()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_08::body::Body;
+
+    use super::*;
+    use crate::webhooks::verify::tests::BODY;
+    use crate::webhooks::{sign_webhook, SIGNATURE_HEADER};
+
+    async fn extract(body: Vec<u8>, signature: String) -> Result<VerifiedWebhook, Response> {
+        let request = Request::builder()
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::from(body))
+            .unwrap();
+
+        VerifiedWebhook::from_request(request, &WebhookSecret::new("secret")).await
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let VerifiedWebhook(event) = extract(BODY.to_vec(), sign_webhook(BODY, b"secret"))
+            .await
+            .unwrap();
+        assert_eq!(event.data.entity_id, "i1");
+    }
+
+    #[tokio::test]
+    async fn test_reject() {
+        let rejection = extract(BODY.to_vec(), sign_webhook(BODY, b"other"))
+            .await
+            .unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+
+        let large = vec![b' '; MAX_BODY_SIZE + 1];
+        let signature = sign_webhook(&large, b"secret");
+        let rejection = extract(large, signature).await.unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
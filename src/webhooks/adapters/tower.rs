@@ -0,0 +1,160 @@
+//! Tower adapter
+//!
+//! [`VerifyWebhookLayer`] buffers the request body, verifies it and passes the
+//! request on with the [`WebhookEvent`] in its extensions. Requests that fail
+//! verification are answered with an empty body and never reach the inner
+//! service. Bodies over [`MAX_BODY_SIZE`] are rejected with `413`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http::{Request, Response, StatusCode};
+use http_body::Body;
+use http_body_util::{BodyExt, Full, Limited};
+use tower::{Layer, Service};
+
+use super::MAX_BODY_SIZE;
+use crate::webhooks::{verify_webhook, WebhookError, WebhookEvent, WebhookSecret};
+
+/// Layer verifying webhook requests
+#[derive(Debug, Clone)]
+pub struct VerifyWebhookLayer {
+    secret: WebhookSecret,
+}
+
+impl VerifyWebhookLayer {
+    /// Create layer verifying with `secret`
+    pub fn new(secret: WebhookSecret) -> Self {
+        Self { secret }
+    }
+}
+
+impl<S> Layer<S> for VerifyWebhookLayer {
+    type Service = VerifyWebhook<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        VerifyWebhook {
+            inner,
+            secret: self.secret.clone(),
+        }
+    }
+}
+
+/// Service verifying webhook requests, see [`VerifyWebhookLayer`]
+#[derive(Debug, Clone)]
+pub struct VerifyWebhook<S> {
+    inner: S,
+    secret: WebhookSecret,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for VerifyWebhook<S>
+where
+    S: Service<Request<Full<Bytes>>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    ReqBody: Body + Send + 'static,
+    ReqBody::Data: Send,
+    ReqBody::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // The clone may not be ready, keep the one that was polled
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let secret = self.secret.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+
+            // Unauthenticated bodies are only buffered up to the limit
+            let bytes = match Limited::new(body, MAX_BODY_SIZE).collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(err) => return Ok(rejection(super::body_error(&*err))),
+            };
+
+            let event: WebhookEvent =
+                match verify_webhook(&parts.headers, &bytes, secret.as_bytes()) {
+                    Ok(event) => event,
+                    Err(err) => return Ok(rejection(err)),
+                };
+
+            parts.extensions.insert(event);
+
+            inner
+                .call(Request::from_parts(parts, Full::new(bytes)))
+                .await
+        })
+    }
+}
+
+fn rejection<B>(err: WebhookError) -> Response<B>
+where
+    B: Default,
+{
+    let mut response = Response::new(B::default());
+    *response.status_mut() =
+        StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::BAD_REQUEST);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+    use crate::webhooks::verify::tests::BODY;
+    use crate::webhooks::{sign_webhook, SIGNATURE_HEADER};
+
+    // Responds with the entity id of the verified event
+    async fn verify(request: Request<Full<Bytes>>) -> Response<String> {
+        let service = service_fn(|request: Request<Full<Bytes>>| async move {
+            let event = request.extensions().get::<WebhookEvent>().cloned();
+            Ok::<_, Infallible>(Response::new(
+                event.map(|event| event.data.entity_id).unwrap_or_default(),
+            ))
+        });
+
+        VerifyWebhookLayer::new(WebhookSecret::new("secret"))
+            .layer(service)
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    fn signed(body: &[u8], secret: &[u8]) -> Request<Full<Bytes>> {
+        Request::builder()
+            .header(SIGNATURE_HEADER, sign_webhook(body, secret))
+            .body(Full::new(Bytes::copy_from_slice(body)))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let response = verify(signed(BODY, b"secret")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "i1");
+    }
+
+    #[tokio::test]
+    async fn test_reject() {
+        let response = verify(signed(BODY, b"other")).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.body(), "");
+
+        let large = vec![b' '; MAX_BODY_SIZE + 1];
+        let response = verify(signed(&large, b"secret")).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
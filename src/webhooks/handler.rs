@@ -0,0 +1,188 @@
+//! Webhook event handlers
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use tokio::sync::mpsc::error::{SendTimeoutError, TrySendError};
+use tokio::sync::mpsc::Sender;
+
+use super::WebhookEvent;
+
+/// Result of handling a webhook event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerResult {
+    /// Event was accepted, Strike is sent `200 OK`
+    Accepted,
+    /// Event could not be handled now, Strike is sent `503 Service
+    /// Unavailable` and will retry delivery
+    Retry,
+    /// Event will never be accepted, Strike is sent `422 Unprocessable
+    /// Entity`
+    Rejected,
+}
+
+impl HandlerResult {
+    pub(crate) fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Accepted => StatusCode::OK,
+            Self::Retry => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Rejected => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+/// Handler of verified webhook events
+#[async_trait]
+pub trait WebhookHandler: Send + Sync {
+    /// Handle event and decide whether it was accepted
+    async fn handle(&self, event: WebhookEvent) -> HandlerResult;
+}
+
+/// How [`ChannelHandler`] sends when the channel is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Wait for capacity
+    Wait,
+    /// Ask Strike to retry if the channel is full
+    TrySend,
+    /// Wait up to the duration for capacity before asking Strike to retry
+    Timeout(Duration),
+}
+
+/// [`WebhookHandler`] that sends the entity id of each event on a channel
+///
+/// Events are acknowledged once they are on the channel. If the receiver has
+/// been dropped, or the channel stays full, Strike is asked to retry.
+#[derive(Debug, Clone)]
+pub struct ChannelHandler {
+    sender: Sender<String>,
+    mode: DeliveryMode,
+}
+
+impl ChannelHandler {
+    /// Create channel handler that waits for capacity
+    pub fn new(sender: Sender<String>) -> Self {
+        Self {
+            sender,
+            mode: DeliveryMode::Wait,
+        }
+    }
+
+    /// Set [`DeliveryMode`]
+    pub fn delivery_mode(mut self, mode: DeliveryMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+#[async_trait]
+impl WebhookHandler for ChannelHandler {
+    async fn handle(&self, event: WebhookEvent) -> HandlerResult {
+        let entity_id = event.data.entity_id;
+
+        let sent = match self.mode {
+            DeliveryMode::Wait => self.sender.send(entity_id).await.map_err(|err| {
+                log::warn!("Could not send on channel: {}", err);
+            }),
+            DeliveryMode::TrySend => self.sender.try_send(entity_id).map_err(|err| match err {
+                TrySendError::Full(_) => log::warn!("Webhook channel is full"),
+                TrySendError::Closed(_) => log::warn!("Webhook channel is closed"),
+            }),
+            DeliveryMode::Timeout(timeout) => self
+                .sender
+                .send_timeout(entity_id, timeout)
+                .await
+                .map_err(|err| match err {
+                    SendTimeoutError::Timeout(_) => {
+                        log::warn!("Timed out waiting for webhook channel capacity")
+                    }
+                    SendTimeoutError::Closed(_) => log::warn!("Webhook channel is closed"),
+                }),
+        };
+
+        match sent {
+            Ok(()) => HandlerResult::Accepted,
+            Err(()) => HandlerResult::Retry,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::webhooks::replay::tests::event;
+
+    async fn handle(handler: &ChannelHandler, id: &str) -> StatusCode {
+        handler
+            .handle(event(id, SystemTime::now()))
+            .await
+            .status_code()
+    }
+
+    #[tokio::test]
+    async fn test_delivered() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let handler = ChannelHandler::new(sender);
+
+        assert_eq!(handle(&handler, "e1").await, StatusCode::OK);
+        assert_eq!(receiver.recv().await.unwrap(), "entity-e1");
+    }
+
+    #[tokio::test]
+    async fn test_closed_channel_retries() {
+        for mode in [
+            DeliveryMode::Wait,
+            DeliveryMode::TrySend,
+            DeliveryMode::Timeout(Duration::from_millis(10)),
+        ] {
+            let (sender, receiver) = mpsc::channel(1);
+            drop(receiver);
+            let handler = ChannelHandler::new(sender).delivery_mode(mode);
+
+            assert_eq!(
+                handle(&handler, "e1").await,
+                StatusCode::SERVICE_UNAVAILABLE
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_channel_retries() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let handler = ChannelHandler::new(sender).delivery_mode(DeliveryMode::TrySend);
+
+        assert_eq!(handle(&handler, "e1").await, StatusCode::OK);
+        assert_eq!(
+            handle(&handler, "e2").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let handler = handler.delivery_mode(DeliveryMode::Timeout(Duration::from_millis(10)));
+        assert_eq!(
+            handle(&handler, "e3").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        // Room again once the receiver catches up
+        assert_eq!(receiver.recv().await.unwrap(), "entity-e1");
+        assert_eq!(handle(&handler, "e4").await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_status_codes() {
+        assert_eq!(HandlerResult::Accepted.status_code(), StatusCode::OK);
+        assert_eq!(
+            HandlerResult::Retry.status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            HandlerResult::Rejected.status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Webhook replay protection
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+
+use super::WebhookEvent;
+use crate::timestamp;
+
+/// Store of webhook event ids that have already been delivered
+///
+/// Implement this over a shared database or cache so that multiple instances
+/// behind a load balancer deduplicate against each other.
+#[async_trait]
+pub trait SeenEventStore: fmt::Debug + Send + Sync {
+    /// Record `event_id` as seen
+    ///
+    /// Returns `false` if the id was already recorded within `window`
+    async fn insert(&self, event_id: &str, window: Duration) -> anyhow::Result<bool>;
+
+    /// Forget `event_id` so a redelivery is not treated as a duplicate
+    async fn remove(&self, event_id: &str) -> anyhow::Result<()>;
+}
+
+/// In memory [`SeenEventStore`]
+#[derive(Debug, Default)]
+pub struct MemorySeenEventStore {
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl MemorySeenEventStore {
+    /// Create empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SeenEventStore for MemorySeenEventStore {
+    async fn insert(&self, event_id: &str, window: Duration) -> anyhow::Result<bool> {
+        let mut seen = self
+            .seen
+            .lock()
+            .map_err(|_| anyhow!("Seen event store lock poisoned"))?;
+
+        let now = Instant::now();
+        seen.retain(|_, at| now.duration_since(*at) < window);
+
+        if seen.contains_key(event_id) {
+            return Ok(false);
+        }
+        seen.insert(event_id.to_string(), now);
+
+        Ok(true)
+    }
+
+    async fn remove(&self, event_id: &str) -> anyhow::Result<()> {
+        self.seen
+            .lock()
+            .map_err(|_| anyhow!("Seen event store lock poisoned"))?
+            .remove(event_id);
+
+        Ok(())
+    }
+}
+
+/// Webhook replay protection options
+#[derive(Debug, Clone)]
+pub struct ReplayProtection {
+    /// Window in which a repeated event id is dropped
+    pub dedup_window: Duration,
+    /// Reject events whose `created` timestamp is older than this
+    ///
+    /// Should not exceed `dedup_window`, otherwise an event forgotten by the
+    /// store can be replayed.
+    pub max_event_age: Option<Duration>,
+    /// Store of seen event ids
+    pub store: Arc<dyn SeenEventStore>,
+}
+
+impl Default for ReplayProtection {
+    fn default() -> Self {
+        Self {
+            dedup_window: Duration::from_secs(24 * 60 * 60),
+            max_event_age: Some(Duration::from_secs(24 * 60 * 60)),
+            store: Arc::new(MemorySeenEventStore::new()),
+        }
+    }
+}
+
+/// Outcome of replay check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Replay {
+    /// First delivery of event
+    New,
+    /// Event id was already delivered
+    Duplicate,
+    /// Event is older than max event age
+    Stale,
+}
+
+impl ReplayProtection {
+    pub(crate) async fn check(&self, event: &WebhookEvent) -> anyhow::Result<Replay> {
+        if let Some(max_age) = self.max_event_age {
+            // An event whose age cannot be established is treated as stale
+            let created = match timestamp::parse(&event.created) {
+                Ok(created) => created,
+                Err(_) => return Ok(Replay::Stale),
+            };
+
+            // Events created in the future (clock skew) are not stale
+            if let Ok(age) = SystemTime::now().duration_since(created) {
+                if age > max_age {
+                    return Ok(Replay::Stale);
+                }
+            }
+        }
+
+        match self.store.insert(&event.id, self.dedup_window).await? {
+            true => Ok(Replay::New),
+            false => Ok(Replay::Duplicate),
+        }
+    }
+
+    pub(crate) async fn forget(&self, event: &WebhookEvent) -> anyhow::Result<()> {
+        self.store.remove(&event.id).await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::events::WebHookData;
+
+    pub(crate) fn event(id: &str, created: SystemTime) -> WebhookEvent {
+        WebhookEvent {
+            id: id.to_string(),
+            event_type: "invoice.updated".to_string(),
+            webhook_version: "v1".to_string(),
+            data: WebHookData {
+                entity_id: format!("entity-{id}"),
+                changes: vec!["state".to_string()],
+            },
+            created: timestamp::format(created),
+            delivery_success: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_dropped() {
+        let protection = ReplayProtection::default();
+        let now = SystemTime::now();
+
+        assert_eq!(
+            protection.check(&event("e1", now)).await.unwrap(),
+            Replay::New
+        );
+        assert_eq!(
+            protection.check(&event("e1", now)).await.unwrap(),
+            Replay::Duplicate
+        );
+        assert_eq!(
+            protection.check(&event("e2", now)).await.unwrap(),
+            Replay::New
+        );
+
+        // A forgotten event is new again
+        protection.forget(&event("e1", now)).await.unwrap();
+        assert_eq!(
+            protection.check(&event("e1", now)).await.unwrap(),
+            Replay::New
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_rejected() {
+        let protection = ReplayProtection {
+            max_event_age: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let now = SystemTime::now();
+
+        let old = event("e1", now - Duration::from_secs(120));
+        assert_eq!(protection.check(&old).await.unwrap(), Replay::Stale);
+
+        // Clock skew does not make an event stale
+        let future = event("e2", now + Duration::from_secs(120));
+        assert_eq!(protection.check(&future).await.unwrap(), Replay::New);
+
+        // Without a max age only duplicates are dropped
+        let protection = ReplayProtection {
+            max_event_age: None,
+            ..Default::default()
+        };
+        assert_eq!(protection.check(&old).await.unwrap(), Replay::New);
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_created_is_stale() {
+        let protection = ReplayProtection::default();
+
+        let mut unparseable = event("e1", SystemTime::now());
+        unparseable.created = "yesterday".to_string();
+        assert_eq!(protection.check(&unparseable).await.unwrap(), Replay::Stale);
+
+        // Stale events are not recorded as seen
+        let fresh = event("e1", SystemTime::now());
+        assert_eq!(protection.check(&fresh).await.unwrap(), Replay::New);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_window() {
+        let store = MemorySeenEventStore::new();
+
+        assert!(store.insert("e1", Duration::from_millis(10)).await.unwrap());
+        assert!(!store.insert("e1", Duration::from_millis(10)).await.unwrap());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(store.insert("e1", Duration::from_millis(10)).await.unwrap());
+    }
+}
@@ -0,0 +1,186 @@
+//! Framework independent webhook verification
+
+use std::collections::HashMap;
+
+use ring::hmac;
+
+use super::WebhookEvent;
+use crate::{hex, Secret};
+
+/// Header carrying the hex encoded HMAC-SHA256 of the request body
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Webhook verification error
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    /// Request did not include a signature
+    #[error("Missing webhook signature")]
+    MissingSignature,
+    /// Signature did not match the body
+    #[error("Invalid webhook signature")]
+    InvalidSignature,
+    /// Body could not be read
+    #[error("Could not read webhook body: {0}")]
+    Body(String),
+    /// Body is over the size limit
+    #[error("Webhook body is too large")]
+    BodyTooLarge,
+    /// Body is not a webhook event
+    #[error("Invalid webhook payload: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+}
+
+impl WebhookError {
+    /// HTTP status code to respond with
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Self::MissingSignature | Self::InvalidSignature => 401,
+            Self::Body(_) => 400,
+            Self::BodyTooLarge => 413,
+            Self::InvalidPayload(_) => 422,
+        }
+    }
+}
+
+/// Webhook secret
+///
+/// Shared with Strike when subscribing, see [`crate::Strike::webhook_secret`]
+#[derive(Debug, Clone)]
+pub struct WebhookSecret(Secret);
+
+impl WebhookSecret {
+    /// Create webhook secret
+    pub fn new<S>(secret: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(Secret::new(secret))
+    }
+
+    /// Secret as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.expose().as_bytes()
+    }
+}
+
+/// Request headers a webhook signature can be read from
+pub trait WebhookHeaders {
+    /// Value of header `name`, matched case insensitively
+    fn header(&self, name: &str) -> Option<&str>;
+}
+
+impl WebhookHeaders for axum::http::HeaderMap {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+impl WebhookHeaders for reqwest::header::HeaderMap {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+impl WebhookHeaders for HashMap<String, String> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl WebhookHeaders for [(&str, &str)] {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Verify webhook signature and parse event
+///
+/// `raw_body` must be the exact bytes Strike sent, before any parsing.
+pub fn verify_webhook<H>(
+    headers: &H,
+    raw_body: &[u8],
+    secret: &[u8],
+) -> Result<WebhookEvent, WebhookError>
+where
+    H: WebhookHeaders + ?Sized,
+{
+    let signature = headers.header(SIGNATURE_HEADER).ok_or_else(|| {
+        log::warn!("Post to webhook did not include signature");
+        WebhookError::MissingSignature
+    })?;
+
+    verify_signature(signature, raw_body, secret)?;
+
+    Ok(serde_json::from_slice(raw_body)?)
+}
+
+/// Verify hex encoded HMAC-SHA256 `signature` of `body`
+pub fn verify_signature(signature: &str, body: &[u8], secret: &[u8]) -> Result<(), WebhookError> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+
+    let signature = hex::decode(signature.trim()).map_err(|_| {
+        log::warn!("Webhook signature is not valid hex");
+        WebhookError::InvalidSignature
+    })?;
+
+    hmac::verify(&key, body, &signature).map_err(|_| {
+        log::warn!("Request did not have a valid signature");
+        WebhookError::InvalidSignature
+    })
+}
+
+/// Hex encoded HMAC-SHA256 signature of `body`, as Strike sends in
+/// [`SIGNATURE_HEADER`]
+pub fn sign_webhook(body: &[u8], secret: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+
+    hmac::sign(&key, body)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const BODY: &[u8] = br#"{"id":"e1","eventType":"invoice.updated","webhookVersion":"v1","data":{"entityId":"i1","changes":["state"]},"created":"2023-03-16T15:30:37.4390395+00:00","deliverySuccess":true}"#;
+
+    #[test]
+    fn test_verify_webhook() {
+        let signature = sign_webhook(BODY, b"secret");
+        let headers = [("x-webhook-signature", signature.as_str())];
+
+        let event = verify_webhook(&headers[..], BODY, b"secret").unwrap();
+        assert_eq!(event.id, "e1");
+        assert_eq!(event.data.entity_id, "i1");
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        let signature = sign_webhook(BODY, b"other");
+        let headers = [(SIGNATURE_HEADER, signature.as_str())];
+
+        assert!(matches!(
+            verify_webhook(&headers[..], BODY, b"secret"),
+            Err(WebhookError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify_signature("zz", BODY, b"secret"),
+            Err(WebhookError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_missing_signature() {
+        assert!(matches!(
+            verify_webhook(&HashMap::new(), BODY, b"secret"),
+            Err(WebhookError::MissingSignature)
+        ));
+    }
+}
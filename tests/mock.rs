@@ -0,0 +1,163 @@
+use std::net::TcpListener;
+use std::time::Duration;
+
+use strike_rs::conversion::{QuotedConversion, Rounding};
+use strike_rs::mock::MockStrike;
+use strike_rs::{Amount, Currency, InvoiceRequest, InvoiceState, PayInvoiceQuoteRequest};
+
+fn invoice_request(amount: f64) -> InvoiceRequest {
+    invoice_request_in(Currency::USD, amount)
+}
+
+fn invoice_request_in(currency: Currency, amount: f64) -> InvoiceRequest {
+    InvoiceRequest {
+        correlation_id: None,
+        description: Some("mock".to_string()),
+        amount: Amount { currency, amount },
+    }
+}
+
+#[tokio::test]
+async fn test_pay_invoice_delivers_webhook() {
+    let mock = MockStrike::start().await.unwrap();
+    let strike = mock.client().unwrap();
+
+    let invoice = strike.create_invoice(invoice_request(10.0)).await.unwrap();
+    let quote = strike.invoice_quote(&invoice.invoice_id).await.unwrap();
+
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(8);
+    let router = strike
+        .create_invoice_webhook_router("/webhook", sender)
+        .await
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let webhook_url = format!("http://{}/webhook", listener.local_addr().unwrap());
+    let server = axum::Server::from_tcp(listener)
+        .unwrap()
+        .serve(router.into_make_service());
+    tokio::spawn(server);
+
+    strike
+        .subscribe_to_invoice_webhook(webhook_url)
+        .await
+        .unwrap();
+
+    let payment_quote = strike
+        .payment_quote(PayInvoiceQuoteRequest {
+            ln_invoice: quote.ln_invoice.clone(),
+            source_currency: Currency::BTC,
+        })
+        .await
+        .unwrap();
+    let payment = strike
+        .pay_quote(&payment_quote.payment_quote_id)
+        .await
+        .unwrap();
+    assert_eq!(payment.state, InvoiceState::Completed);
+
+    let entity_id = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entity_id, invoice.invoice_id);
+
+    let invoice = strike
+        .get_incoming_invoice(&invoice.invoice_id)
+        .await
+        .unwrap();
+    assert_eq!(invoice.state, InvoiceState::Paid);
+    assert_eq!(mock.events()[0].delivery_success, Some(true));
+
+    // A quote can only be executed once
+    assert!(strike
+        .pay_quote(&payment_quote.payment_quote_id)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_idempotency_key_replays_response() {
+    let mock = MockStrike::start().await.unwrap();
+    let strike = mock.client().unwrap();
+
+    let first = strike.create_invoice(invoice_request(1.0)).await.unwrap();
+
+    // Repeating the call with its key replays the response
+    let keyed = strike.with_idempotency_key(first.idempotency_key.clone());
+    let repeat = keyed.create_invoice(invoice_request(1.0)).await.unwrap();
+    assert_eq!(repeat.invoice_id, first.invoice_id);
+    assert_eq!(repeat.idempotency_key, first.idempotency_key);
+
+    // The key is only used once
+    let next = keyed.create_invoice(invoice_request(1.0)).await.unwrap();
+    assert_ne!(next.invoice_id, first.invoice_id);
+    assert_ne!(next.idempotency_key, first.idempotency_key);
+}
+
+#[tokio::test]
+async fn test_failures_are_retried() {
+    let mock = MockStrike::start().await.unwrap();
+    let strike = mock.client().unwrap();
+
+    let invoice = strike.create_invoice(invoice_request(1.0)).await.unwrap();
+
+    mock.fail_next(2, 503).unwrap();
+    let fetched = strike
+        .get_incoming_invoice(&invoice.invoice_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched.invoice_id, invoice.invoice_id);
+
+    mock.fail_next(3, 503).unwrap();
+    assert!(strike
+        .get_incoming_invoice(&invoice.invoice_id)
+        .await
+        .is_err());
+
+    assert!(strike.get_incoming_invoice("unknown").await.is_err());
+}
+
+#[tokio::test]
+async fn test_payment_quote_conversion() {
+    let mock = MockStrike::start().await.unwrap();
+    let strike = mock.client().unwrap();
+
+    // USD pays a BTC invoice of 100000 sats at 50000 USD per BTC
+    let invoice = strike
+        .create_invoice(invoice_request_in(Currency::BTC, 0.001))
+        .await
+        .unwrap();
+    let quote = strike.invoice_quote(&invoice.invoice_id).await.unwrap();
+    let payment_quote = strike
+        .payment_quote(PayInvoiceQuoteRequest {
+            ln_invoice: quote.ln_invoice.clone(),
+            source_currency: Currency::USD,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        payment_quote.amount,
+        Amount {
+            currency: Currency::USD,
+            amount: 50.0
+        }
+    );
+    assert_eq!(
+        payment_quote
+            .amount_in_sats(&payment_quote.amount, Rounding::Nearest)
+            .unwrap(),
+        100_000
+    );
+    assert_eq!(
+        payment_quote
+            .convert_amount(
+                &Amount::from_sats(100_000),
+                Currency::USD,
+                Rounding::Nearest
+            )
+            .unwrap()
+            .amount,
+        50.0
+    );
+}